@@ -0,0 +1,38 @@
+use bytelink_crypto::batch::verify_batch;
+use bytelink_crypto::signer::{Signer, SignerSync};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::hint::black_box;
+
+fn signature_verification(c: &mut Criterion) {
+    let mut group = c.benchmark_group("signature_verification");
+
+    for size in [64, 256, 1024] {
+        let batch = (0..size)
+            .map(|i| {
+                let signer = Signer::random();
+                let msg = format!("transaction-{i}").into_bytes();
+                (signer.eip191_sign_msg(&msg).unwrap(), msg, signer.address())
+            })
+            .collect::<Vec<_>>();
+
+        group.throughput(Throughput::Elements(size as u64));
+
+        group.bench_with_input(BenchmarkId::new("sequential", size), &batch, |b, batch| {
+            b.iter(|| {
+                for (signature, msg, expected) in batch {
+                    let address = signature.recover_address_from_msg(msg).unwrap();
+                    assert_eq!(&address, expected);
+                }
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("batch", size), &batch, |b, batch| {
+            b.iter(|| verify_batch(black_box(batch)).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, signature_verification);
+criterion_main!(benches);
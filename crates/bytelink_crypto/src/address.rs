@@ -3,6 +3,7 @@ use anyhow::Result;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::fmt::Formatter;
+use std::str::FromStr;
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Hash)]
 #[repr(transparent)]
@@ -14,6 +15,12 @@ impl From<alloy_primitives::Address> for Address {
     }
 }
 
+impl From<[u8; 20]> for Address {
+    fn from(bytes: [u8; 20]) -> Self {
+        Address(alloy_primitives::Address::from(bytes))
+    }
+}
+
 impl Address {
     pub fn random<R: Rng>(mut rng: R) -> Self {
         let bytes: [u8; 20] = rng.random();
@@ -28,15 +35,82 @@ impl Address {
         Ok(Address(inner))
     }
 
+    /// Parses an address checksummed with the chain id as described in
+    /// [EIP-1191](https://eips.ethereum.org/EIPS/eip-1191), it must start with "0x".
+    pub fn parse_checksummed_with_chain_id<S: AsRef<str>>(hex: S, chain_id: u64) -> Result<Self> {
+        let inner = alloy_primitives::Address::parse_checksummed(hex, Some(chain_id))?;
+        Ok(Address(inner))
+    }
+
+    /// Create an address from its raw 20 bytes.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        let Ok(bytes) = <[u8; 20]>::try_from(bytes) else {
+            anyhow::bail!(
+                "Invalid address length: expected 20 bytes, got {}",
+                bytes.len()
+            );
+        };
+        Ok(Address::from(bytes))
+    }
+
     pub fn from_private_key(key: &SigningKey) -> Self {
         let inner = alloy_primitives::Address::from_private_key(key);
         Address(inner)
     }
 
+    /// return the raw 20 bytes of the address
+    pub fn as_bytes(&self) -> &[u8; 20] {
+        self.0.as_ref()
+    }
+
     /// return the hex string representation of the checksummed address
     pub fn checksummed(&self) -> String {
         self.0.to_checksum(None)
     }
+
+    /// return the hex string representation of the address checksummed with the chain id,
+    /// see [EIP-1191](https://eips.ethereum.org/EIPS/eip-1191)
+    pub fn checksummed_with_chain_id(&self, chain_id: u64) -> String {
+        self.0.to_checksum(Some(chain_id))
+    }
+}
+
+impl AsRef<Address> for Address {
+    fn as_ref(&self) -> &Address {
+        self
+    }
+}
+
+/// Parses an address with an optional "0x" prefix.
+///
+/// An all-lowercase or all-uppercase address carries no checksum and is accepted as is,
+/// while a mixed-case address must have a valid [EIP-55](https://eips.ethereum.org/EIPS/eip-55) checksum.
+impl FromStr for Address {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let hex = s.strip_prefix("0x").unwrap_or(s);
+        if hex.len() != 40 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            anyhow::bail!("Invalid address: expected 40 hex characters, got {s}");
+        }
+
+        let has_lower = hex.chars().any(|c| c.is_ascii_lowercase());
+        let has_upper = hex.chars().any(|c| c.is_ascii_uppercase());
+        if has_lower && has_upper {
+            return Self::parse_checksummed(format!("0x{hex}"));
+        }
+
+        let mut bytes = [0u8; 20];
+        alloy_primitives::hex::decode_to_slice(hex, &mut bytes)?;
+        Ok(Address::from(bytes))
+    }
+}
+
+/// Displays the checksummed address.
+impl std::fmt::Display for Address {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.checksummed())
+    }
 }
 
 /// Hex representation tests for Address
@@ -82,6 +156,70 @@ mod tests {
         assert!(Address::parse_checksummed("0xf01813e4b85e178a83e29b8e7bf26bd830a25f32").is_err());
     }
 
+    #[test]
+    fn parse_unchecksummed_address() {
+        let expected =
+            Address::parse_checksummed("0xF01813E4B85e178A83e29B8E7bF26BD830a25f32").unwrap();
+        for s in [
+            "0xf01813e4b85e178a83e29b8e7bf26bd830a25f32",
+            "0xF01813E4B85E178A83E29B8E7BF26BD830A25F32",
+            "f01813e4b85e178a83e29b8e7bf26bd830a25f32",
+            "0xF01813E4B85e178A83e29B8E7bF26BD830a25f32",
+        ] {
+            assert_eq!(s.parse::<Address>().unwrap(), expected);
+        }
+        assert_eq!(expected.to_string(), expected.checksummed());
+    }
+
+    #[test]
+    fn reject_invalid_address() {
+        // mixed case with a wrong checksum
+        assert!(
+            "0xf01813E4B85e178A83e29B8E7bF26BD830a25f32"
+                .parse::<Address>()
+                .is_err()
+        );
+        // too short, too long, not hex
+        assert!(
+            "0xf01813e4b85e178a83e29b8e7bf26bd830a25f3"
+                .parse::<Address>()
+                .is_err()
+        );
+        assert!(
+            "0xf01813e4b85e178a83e29b8e7bf26bd830a25f321"
+                .parse::<Address>()
+                .is_err()
+        );
+        assert!(
+            "0xg01813e4b85e178a83e29b8e7bf26bd830a25f32"
+                .parse::<Address>()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn eip1191_checksum() {
+        // test vectors of EIP-1191
+        let vectors = [
+            (30, "0x5aaEB6053f3e94c9b9a09f33669435E7ef1bEAeD"),
+            (30, "0xFb6916095cA1Df60bb79ce92cE3EA74c37c5d359"),
+            (31, "0x5aAeb6053F3e94c9b9A09F33669435E7EF1BEaEd"),
+            (31, "0xFb6916095CA1dF60bb79CE92ce3Ea74C37c5D359"),
+        ];
+        for (chain_id, s) in vectors {
+            let address = Address::parse_checksummed_with_chain_id(s, chain_id).unwrap();
+            assert_eq!(address.checksummed_with_chain_id(chain_id), s);
+            assert!(Address::parse_checksummed_with_chain_id(s, 1).is_err());
+        }
+    }
+
+    #[test]
+    fn from_slice() {
+        let address = Address::random(rng());
+        assert_eq!(Address::from_slice(address.as_bytes()).unwrap(), address);
+        assert!(Address::from_slice(&[0u8; 19]).is_err());
+    }
+
     #[test]
     fn generate_random_address() {
         let addr1 = Address::random(rng());
@@ -0,0 +1,111 @@
+use crate::address::Address;
+use crate::signature::Signature;
+use anyhow::Result;
+use rayon::prelude::*;
+use std::fmt::Formatter;
+
+/// The positions of the items that failed a batch verification, in ascending order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchError {
+    pub failed: Vec<usize>,
+}
+
+impl BatchError {
+    /// `Ok` if no index failed, the error otherwise.
+    pub fn from_failed(failed: Vec<usize>) -> std::result::Result<(), BatchError> {
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(BatchError { failed })
+        }
+    }
+}
+
+impl std::fmt::Display for BatchError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Batch verification failed at indices {:?}", self.failed)
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+/// Recover the signers of many [EIP-191](https://eips.ethereum.org/EIPS/eip-191) messages
+/// in parallel on the global thread pool, see [`Signature::recover_address_from_msg`].
+/// The results are in the same order as the input.
+pub fn recover_addresses<M>(items: &[(Signature, M)]) -> Vec<Result<Address>>
+where
+    M: AsRef<[u8]> + Sync,
+{
+    items
+        .par_iter()
+        .map(|(signature, msg)| signature.recover_address_from_msg(msg.as_ref()))
+        .collect()
+}
+
+/// Verify in parallel that every message was signed by the expected address,
+/// reporting all the items that did not recover to it.
+pub fn verify_batch<M>(items: &[(Signature, M, Address)]) -> std::result::Result<(), BatchError>
+where
+    M: AsRef<[u8]> + Sync,
+{
+    let failed = items
+        .par_iter()
+        .enumerate()
+        .filter(|(_, (signature, msg, expected))| {
+            !matches!(signature.recover_address_from_msg(msg.as_ref()), Ok(addr) if &addr == expected)
+        })
+        .map(|(index, _)| index)
+        .collect();
+    BatchError::from_failed(failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::batch::{BatchError, recover_addresses, verify_batch};
+    use crate::signer::{Signer, SignerSync};
+
+    fn signed(n: usize) -> Vec<(Signer, Vec<u8>)> {
+        (0..n)
+            .map(|i| (Signer::random(), format!("msg-{i}").into_bytes()))
+            .collect()
+    }
+
+    #[test]
+    fn recover_in_order() {
+        let items = signed(16);
+        let batch = items
+            .iter()
+            .map(|(signer, msg)| (signer.eip191_sign_msg(msg).unwrap(), msg.clone()))
+            .collect::<Vec<_>>();
+
+        let recovered = recover_addresses(&batch);
+        for ((signer, _), address) in items.iter().zip(recovered) {
+            assert_eq!(address.unwrap(), signer.address());
+        }
+    }
+
+    #[test]
+    fn verify_valid_batch() {
+        let batch = signed(16)
+            .into_iter()
+            .map(|(signer, msg)| (signer.eip191_sign_msg(&msg).unwrap(), msg, signer.address()))
+            .collect::<Vec<_>>();
+        assert!(verify_batch(&batch).is_ok());
+        assert!(verify_batch::<Vec<u8>>(&[]).is_ok());
+    }
+
+    #[test]
+    fn report_failed_indices() {
+        let mut batch = signed(8)
+            .into_iter()
+            .map(|(signer, msg)| (signer.eip191_sign_msg(&msg).unwrap(), msg, signer.address()))
+            .collect::<Vec<_>>();
+
+        // message that was not signed
+        batch[1].1 = b"tampered".to_vec();
+        // signed by someone else
+        batch[6].2 = Signer::random().address();
+
+        assert_eq!(verify_batch(&batch), Err(BatchError { failed: vec![1, 6] }));
+    }
+}
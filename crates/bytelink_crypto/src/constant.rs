@@ -1 +1,7 @@
+use alloy_primitives::{U256, uint};
+
 pub const EIP191_PREFIX: &str = "\x19Bytelink Signed Message:\n";
+
+/// The order `n` of the secp256k1 curve.
+pub const SECP256K1N_ORDER: U256 =
+    uint!(0xFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141_U256);
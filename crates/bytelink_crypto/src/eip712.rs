@@ -0,0 +1,310 @@
+use crate::address::Address;
+use alloy_primitives::{B256, U256, keccak256};
+
+/// The domain of [EIP-712](https://eips.ethereum.org/EIPS/eip-712) typed data,
+/// which prevents a signature from being replayed on another dApp or another chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Eip712Domain {
+    /// user readable name of the signing domain, i.e. the name of the dApp
+    pub name: String,
+    /// current major version of the signing domain
+    pub version: String,
+    /// the bytelink chain id, see the genesis
+    pub chain_id: u64,
+    /// address of the contract that will verify the signature, if any
+    pub verifying_contract: Option<Address>,
+}
+
+impl Eip712Domain {
+    pub fn new<N: Into<String>, V: Into<String>>(name: N, version: V, chain_id: u64) -> Self {
+        Eip712Domain {
+            name: name.into(),
+            version: version.into(),
+            chain_id,
+            verifying_contract: None,
+        }
+    }
+
+    pub fn with_verifying_contract(mut self, contract: Address) -> Self {
+        self.verifying_contract = Some(contract);
+        self
+    }
+
+    /// The `encodeType` of the domain, which only lists the fields in use.
+    fn encode_type(&self) -> String {
+        let mut ty = String::from("EIP712Domain(string name,string version,uint256 chainId");
+        if self.verifying_contract.is_some() {
+            ty.push_str(",address verifyingContract");
+        }
+        ty.push(')');
+        ty
+    }
+
+    /// The domain separator, which is the `hashStruct` of the domain.
+    pub fn separator(&self) -> B256 {
+        let mut buf = Vec::with_capacity(5 * 32);
+        buf.extend_from_slice(keccak256(self.encode_type()).as_slice());
+        buf.extend_from_slice(self.name.encode_eip712().as_slice());
+        buf.extend_from_slice(self.version.encode_eip712().as_slice());
+        buf.extend_from_slice(self.chain_id.encode_eip712().as_slice());
+        if let Some(contract) = &self.verifying_contract {
+            buf.extend_from_slice(contract.encode_eip712().as_slice());
+        }
+        keccak256(buf)
+    }
+}
+
+/// A struct that can be signed as [EIP-712](https://eips.ethereum.org/EIPS/eip-712) typed data.
+/// ```
+/// use bytelink_crypto::address::Address;
+/// use bytelink_crypto::eip712::{Eip712Encode, TypedData};
+///
+/// struct Order {
+///     maker: Address,
+///     amount: u64,
+/// }
+///
+/// impl TypedData for Order {
+///     fn encode_type() -> String {
+///         "Order(address maker,uint64 amount)".to_string()
+///     }
+///
+///     fn encode_data(&self) -> Vec<u8> {
+///         [self.maker.encode_eip712(), self.amount.encode_eip712()].concat()
+///     }
+/// }
+/// ```
+pub trait TypedData {
+    /// The `encodeType` of the struct, i.e. `Name(type1 member1,type2 member2)`, followed
+    /// by the `encodeType` of every struct it references, sorted by name.
+    fn encode_type() -> String;
+
+    /// The `encodeData` of the struct, i.e. the concatenation of its
+    /// [encoded][Eip712Encode::encode_eip712] members in declaration order.
+    fn encode_data(&self) -> Vec<u8>;
+
+    fn type_hash() -> B256 {
+        keccak256(Self::encode_type())
+    }
+
+    /// The `hashStruct` of the struct, which is `keccak256(typeHash | encodeData)`.
+    fn hash_struct(&self) -> B256 {
+        let data = self.encode_data();
+        let mut buf = Vec::with_capacity(32 + data.len());
+        buf.extend_from_slice(Self::type_hash().as_slice());
+        buf.extend_from_slice(&data);
+        keccak256(buf)
+    }
+}
+
+/// Encoding of a member of [`TypedData`] into a 32-byte word.
+///
+/// Atomic values are left padded, dynamic values (`string`, `bytes`) are hashed
+/// and nested structs are replaced by their [`hash_struct`][TypedData::hash_struct].
+pub trait Eip712Encode {
+    fn encode_eip712(&self) -> B256;
+}
+
+impl<T: TypedData> Eip712Encode for T {
+    fn encode_eip712(&self) -> B256 {
+        self.hash_struct()
+    }
+}
+
+impl Eip712Encode for Address {
+    fn encode_eip712(&self) -> B256 {
+        let mut word = B256::ZERO;
+        word[12..].copy_from_slice(self.as_bytes());
+        word
+    }
+}
+
+impl Eip712Encode for bool {
+    fn encode_eip712(&self) -> B256 {
+        U256::from(*self as u8).into()
+    }
+}
+
+impl Eip712Encode for u64 {
+    fn encode_eip712(&self) -> B256 {
+        U256::from(*self).into()
+    }
+}
+
+impl Eip712Encode for u128 {
+    fn encode_eip712(&self) -> B256 {
+        U256::from(*self).into()
+    }
+}
+
+impl Eip712Encode for U256 {
+    fn encode_eip712(&self) -> B256 {
+        (*self).into()
+    }
+}
+
+impl Eip712Encode for B256 {
+    fn encode_eip712(&self) -> B256 {
+        *self
+    }
+}
+
+impl Eip712Encode for str {
+    fn encode_eip712(&self) -> B256 {
+        keccak256(self)
+    }
+}
+
+impl Eip712Encode for String {
+    fn encode_eip712(&self) -> B256 {
+        self.as_str().encode_eip712()
+    }
+}
+
+impl Eip712Encode for [u8] {
+    fn encode_eip712(&self) -> B256 {
+        keccak256(self)
+    }
+}
+
+impl Eip712Encode for Vec<u8> {
+    fn encode_eip712(&self) -> B256 {
+        self.as_slice().encode_eip712()
+    }
+}
+
+/// Create the Keccak-256 signing hash of [EIP-712](https://eips.ethereum.org/EIPS/eip-712) typed data.
+/// ```text
+/// ┌───────────┬──────────────────┬──────────────────┐
+/// │ \x19\x01  │ domainSeparator  │  hashStruct(msg) │
+/// └───────────┴──────────────────┴──────────────────┘
+/// ```
+pub fn eip712_hash<T: TypedData>(domain: &Eip712Domain, data: &T) -> B256 {
+    let mut buf = [0u8; 2 + 32 + 32];
+    buf[..2].copy_from_slice(b"\x19\x01");
+    buf[2..34].copy_from_slice(domain.separator().as_slice());
+    buf[34..].copy_from_slice(data.hash_struct().as_slice());
+    keccak256(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::address::Address;
+    use crate::eip712::{Eip712Domain, Eip712Encode, TypedData, eip712_hash};
+    use crate::signature::Signature;
+    use crate::signer::{Signer, SignerSync};
+    use alloy_primitives::{b256, keccak256};
+
+    // the example of the EIP-712 specification
+    struct Person {
+        name: String,
+        wallet: Address,
+    }
+
+    struct Mail {
+        from: Person,
+        to: Person,
+        contents: String,
+    }
+
+    impl TypedData for Person {
+        fn encode_type() -> String {
+            "Person(string name,address wallet)".to_string()
+        }
+
+        fn encode_data(&self) -> Vec<u8> {
+            [self.name.encode_eip712(), self.wallet.encode_eip712()].concat()
+        }
+    }
+
+    impl TypedData for Mail {
+        fn encode_type() -> String {
+            format!(
+                "Mail(Person from,Person to,string contents){}",
+                Person::encode_type()
+            )
+        }
+
+        fn encode_data(&self) -> Vec<u8> {
+            [
+                self.from.encode_eip712(),
+                self.to.encode_eip712(),
+                self.contents.encode_eip712(),
+            ]
+            .concat()
+        }
+    }
+
+    fn domain() -> Eip712Domain {
+        let contract =
+            Address::parse_checksummed("0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC").unwrap();
+        Eip712Domain::new("Ether Mail", "1", 1).with_verifying_contract(contract)
+    }
+
+    fn mail() -> Mail {
+        Mail {
+            from: Person {
+                name: "Cow".to_string(),
+                wallet: Address::parse_checksummed("0xCD2a3d9F938E13CD947Ec05AbC7FE734Df8DD826")
+                    .unwrap(),
+            },
+            to: Person {
+                name: "Bob".to_string(),
+                wallet: Address::parse_checksummed("0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB")
+                    .unwrap(),
+            },
+            contents: "Hello, Bob!".to_string(),
+        }
+    }
+
+    fn cow() -> Signer {
+        Signer::from_slice(keccak256("cow").as_slice()).unwrap()
+    }
+
+    #[test]
+    fn specification_vector() {
+        assert_eq!(
+            domain().separator(),
+            b256!("0xf2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f")
+        );
+        assert_eq!(
+            mail().hash_struct(),
+            b256!("0xc52c0ee5d84264471806290a3f2c4cecfc5490626bf912d01f240d7a274b371e")
+        );
+        assert_eq!(
+            eip712_hash(&domain(), &mail()),
+            b256!("0xbe609aee343fb3c4b28e1df9e632fca64fcfaede20f02e86244efddf30957bd2")
+        );
+    }
+
+    #[test]
+    fn specification_signature() {
+        let signature = cow().eip712_sign(&domain(), &mail()).unwrap();
+        let expected = Signature::from_hex(
+            "0x4355c47d63924e8a72e509b65029052eb6c299d53a04e167c5775fd466751c9d\
+             07299936d304c153f6443dfa05f40ff007d72911b6f72307f996231605b91562\
+             1c",
+        )
+        .unwrap();
+        assert_eq!(signature, expected);
+        assert_eq!(
+            signature
+                .recover_address_from_typed_data(&domain(), &mail())
+                .unwrap(),
+            cow().address()
+        );
+    }
+
+    #[test]
+    fn chain_id_is_part_of_the_domain() {
+        let bytelink = Eip712Domain::new("Ether Mail", "1", 2);
+        let other = Eip712Domain::new("Ether Mail", "1", 3);
+        assert_ne!(bytelink.separator(), other.separator());
+
+        let signature = cow().eip712_sign(&bytelink, &mail()).unwrap();
+        let recovered = signature
+            .recover_address_from_typed_data(&other, &mail())
+            .unwrap();
+        assert_ne!(recovered, cow().address());
+    }
+}
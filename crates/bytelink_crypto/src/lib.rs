@@ -1,5 +1,11 @@
 pub mod address;
+pub mod batch;
 pub mod constant;
 pub mod eip191;
+pub mod eip712;
+pub mod merkle;
+#[cfg(all(unix, feature = "remote-signer"))]
+pub mod remote;
 pub mod signature;
 pub mod signer;
+pub mod smt;
@@ -0,0 +1,254 @@
+use alloy_primitives::{B256, KECCAK256_EMPTY, keccak256};
+use serde::{Deserialize, Serialize};
+
+/// Domain separation tags, so that a leaf can never be reinterpreted as an inner node
+/// (second pre-image attack on Merkle trees).
+const LEAF_TAG: u8 = 0x00;
+const NODE_TAG: u8 = 0x01;
+
+/// Hash a leaf of the Merkle tree, which is `keccak256(0x00 | data)`.
+pub fn leaf_hash<T: AsRef<[u8]>>(data: T) -> B256 {
+    let data = data.as_ref();
+    let mut buf = Vec::with_capacity(1 + data.len());
+    buf.push(LEAF_TAG);
+    buf.extend_from_slice(data);
+    keccak256(buf)
+}
+
+/// Hash two children of the Merkle tree, which is `keccak256(0x01 | left | right)`.
+fn node_hash(left: &B256, right: &B256) -> B256 {
+    let mut buf = [0u8; 65];
+    buf[0] = NODE_TAG;
+    buf[1..33].copy_from_slice(left.as_slice());
+    buf[33..].copy_from_slice(right.as_slice());
+    keccak256(buf)
+}
+
+/// A binary Merkle tree using Keccak-256.
+///
+/// When a layer has an odd number of nodes, the last node is promoted to the next layer
+/// as is instead of being hashed with itself, so that two different lists of leaves can
+/// never share the same root.
+/// ```text
+///              root
+///            /      \
+///        h(ab)       c
+///        /   \       |
+///       a     b      c
+/// ```
+/// The root of an empty tree is the Keccak-256 hash of the empty string.
+#[derive(Debug, Clone)]
+pub struct MerkleTree {
+    /// layers of the tree, from the leaves (`layers[0]`) up to the root
+    layers: Vec<Vec<B256>>,
+}
+
+impl MerkleTree {
+    /// Build a Merkle tree from the raw leaf data.
+    pub fn new<I, T>(leaves: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<[u8]>,
+    {
+        Self::from_leaf_hashes(leaves.into_iter().map(leaf_hash).collect())
+    }
+
+    /// Build a Merkle tree from already hashed leaves, see [`leaf_hash`].
+    pub fn from_leaf_hashes(leaves: Vec<B256>) -> Self {
+        let mut layers = vec![leaves];
+        while layers.last().is_some_and(|layer| layer.len() > 1) {
+            let layer = layers.last().unwrap();
+            let next = layer
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => node_hash(left, right),
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+            layers.push(next);
+        }
+        MerkleTree { layers }
+    }
+
+    /// number of leaves in the tree.
+    pub fn len(&self) -> usize {
+        self.layers[0].len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn root(&self) -> B256 {
+        match self.layers.last().and_then(|layer| layer.first()) {
+            Some(root) => *root,
+            None => KECCAK256_EMPTY,
+        }
+    }
+
+    /// Create an inclusion proof for the leaf at `index`, `None` if out of bounds.
+    pub fn proof(&self, index: usize) -> Option<MerkleProof> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let mut siblings = Vec::with_capacity(self.layers.len());
+        let mut idx = index;
+        for layer in &self.layers[..self.layers.len() - 1] {
+            let sibling = idx ^ 1;
+            // the last node of an odd layer has no sibling, it is promoted as is
+            if sibling < layer.len() {
+                siblings.push(layer[sibling]);
+            }
+            idx /= 2;
+        }
+
+        Some(MerkleProof {
+            index,
+            leaves: self.len(),
+            siblings,
+        })
+    }
+}
+
+/// Compute the Merkle root of the given leaves without keeping the tree around.
+pub fn merkle_root<I, T>(leaves: I) -> B256
+where
+    I: IntoIterator<Item = T>,
+    T: AsRef<[u8]>,
+{
+    MerkleTree::new(leaves).root()
+}
+
+/// An inclusion proof of a single leaf in a [`MerkleTree`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    /// position of the leaf in the tree
+    pub index: usize,
+    /// total number of leaves in the tree
+    pub leaves: usize,
+    /// sibling hashes from the bottom of the tree up to the root
+    pub siblings: Vec<B256>,
+}
+
+impl MerkleProof {
+    /// Verify that `data` is the leaf at [`index`][MerkleProof::index] of the tree with the given `root`.
+    pub fn verify<T: AsRef<[u8]>>(&self, root: &B256, data: T) -> bool {
+        self.verify_leaf_hash(root, &leaf_hash(data))
+    }
+
+    /// Same as [`verify`][MerkleProof::verify] but with an already hashed leaf.
+    pub fn verify_leaf_hash(&self, root: &B256, leaf: &B256) -> bool {
+        if self.index >= self.leaves {
+            return false;
+        }
+
+        let mut siblings = self.siblings.iter();
+        let mut hash = *leaf;
+        let mut idx = self.index;
+        let mut width = self.leaves;
+        while width > 1 {
+            if idx % 2 == 1 {
+                match siblings.next() {
+                    Some(left) => hash = node_hash(left, &hash),
+                    None => return false,
+                }
+            } else if idx + 1 < width {
+                match siblings.next() {
+                    Some(right) => hash = node_hash(&hash, right),
+                    None => return false,
+                }
+            }
+            idx /= 2;
+            width = width.div_ceil(2);
+        }
+
+        // all the siblings must be consumed
+        siblings.next().is_none() && hash == *root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::merkle::{MerkleTree, leaf_hash, merkle_root, node_hash};
+    use alloy_primitives::KECCAK256_EMPTY;
+
+    fn leaves(n: usize) -> Vec<Vec<u8>> {
+        (0..n).map(|i| format!("tx-{i}").into_bytes()).collect()
+    }
+
+    #[test]
+    fn empty_tree() {
+        let tree = MerkleTree::new(Vec::<Vec<u8>>::new());
+        assert!(tree.is_empty());
+        assert_eq!(tree.root(), KECCAK256_EMPTY);
+        assert!(tree.proof(0).is_none());
+    }
+
+    #[test]
+    fn single_leaf_root_is_leaf_hash() {
+        let tree = MerkleTree::new([b"tx-0"]);
+        assert_eq!(tree.root(), leaf_hash(b"tx-0"));
+    }
+
+    #[test]
+    fn odd_leaf_is_promoted() {
+        let (a, b, c) = (leaf_hash(b"a"), leaf_hash(b"b"), leaf_hash(b"c"));
+        let expected = node_hash(&node_hash(&a, &b), &c);
+        assert_eq!(merkle_root([b"a", b"b", b"c"]), expected);
+    }
+
+    #[test]
+    fn duplicated_last_leaf_changes_root() {
+        assert_ne!(
+            merkle_root([b"a", b"b", b"c"]),
+            merkle_root([b"a", b"b", b"c", b"c"])
+        );
+    }
+
+    #[test]
+    fn prove_and_verify_every_leaf() {
+        for n in 1..=17 {
+            let data = leaves(n);
+            let tree = MerkleTree::new(&data);
+            let root = tree.root();
+            for (i, leaf) in data.iter().enumerate() {
+                let proof = tree.proof(i).unwrap();
+                assert!(proof.verify(&root, leaf), "leaf {i} of {n}");
+            }
+        }
+    }
+
+    #[test]
+    fn reject_wrong_leaf() {
+        let data = leaves(5);
+        let tree = MerkleTree::new(&data);
+        let proof = tree.proof(2).unwrap();
+        assert!(!proof.verify(&tree.root(), &data[3]));
+        assert!(!proof.verify(&tree.root(), b"not-a-tx"));
+    }
+
+    #[test]
+    fn reject_tampered_proof() {
+        let data = leaves(6);
+        let tree = MerkleTree::new(&data);
+        let root = tree.root();
+
+        let mut wrong_index = tree.proof(1).unwrap();
+        wrong_index.index = 0;
+        assert!(!wrong_index.verify(&root, &data[1]));
+
+        let mut truncated = tree.proof(1).unwrap();
+        truncated.siblings.pop();
+        assert!(!truncated.verify(&root, &data[1]));
+
+        let mut extended = tree.proof(1).unwrap();
+        extended.siblings.push(root);
+        assert!(!extended.verify(&root, &data[1]));
+
+        let mut out_of_bounds = tree.proof(5).unwrap();
+        out_of_bounds.index = 6;
+        assert!(!out_of_bounds.verify(&root, &data[5]));
+    }
+}
@@ -0,0 +1,234 @@
+use crate::address::Address;
+use crate::signature::Signature;
+use crate::signer::{AsyncSigner, Signer, SignerSync};
+use alloy_primitives::B256;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::Mutex;
+
+/// A request to the signing daemon, one JSON object per line.
+/// ```json
+/// {"method":"address"}
+/// {"method":"sign_hash","hash":"0x.."}
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum Request {
+    Address,
+    SignHash { hash: B256 },
+}
+
+/// The response of the signing daemon, one JSON object per line.
+/// ```json
+/// {"result":"address","address":"0x.."}
+/// {"result":"signature","signature":"0x.."}
+/// {"result":"error","message":".."}
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "result", rename_all = "snake_case")]
+enum Response {
+    Address { address: Address },
+    Signature { signature: Signature },
+    Error { message: String },
+}
+
+/// Bind the socket of a signing daemon at `path`, accessible to its owner only.
+///
+/// The socket is created inside a fresh private directory and made owner-only before it is
+/// linked at `path`, so nobody can connect to it before its permissions are restricted.
+pub fn bind<P: AsRef<Path>>(path: P) -> Result<UnixListener> {
+    let path = path.as_ref();
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid socket path: {}", path.display()))?;
+    let id: u64 = rand::random();
+    let private = path.with_file_name(format!(".{}.{id:x}", name.to_string_lossy()));
+    std::fs::DirBuilder::new().mode(0o700).create(&private)?;
+
+    let staged = private.join("signer.sock");
+    let bound = UnixListener::bind(&staged).and_then(|listener| {
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o600))?;
+        // unlike a rename, linking never replaces an existing file
+        std::fs::hard_link(&staged, path)?;
+        Ok(listener)
+    });
+    let _ = std::fs::remove_file(&staged);
+    std::fs::remove_dir(&private)?;
+    Ok(bound?)
+}
+
+/// Run a signing daemon on the local socket, signing every requested hash with the signer.
+///
+/// Anyone able to connect to the socket can sign with the key, so the socket should be
+/// created with [`bind`].
+pub async fn serve(listener: UnixListener, signer: Arc<Signer>) -> Result<()> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let signer = signer.clone();
+        tokio::spawn(async move {
+            // a broken connection only concerns its client
+            let _ = handle(stream, signer.as_ref()).await;
+        });
+    }
+}
+
+async fn handle(stream: UnixStream, signer: &Signer) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        let response = match serde_json::from_str(&line) {
+            Ok(Request::Address) => Response::Address {
+                address: signer.address(),
+            },
+            Ok(Request::SignHash { hash }) => match signer.sign_hash_sync(&hash) {
+                Ok(signature) => Response::Signature { signature },
+                Err(err) => Response::Error {
+                    message: err.to_string(),
+                },
+            },
+            Err(err) => Response::Error {
+                message: format!("Invalid request: {err}"),
+            },
+        };
+        let mut json = serde_json::to_vec(&response)?;
+        json.push(b'\n');
+        writer.write_all(&json).await?;
+    }
+
+    Ok(())
+}
+
+/// The client of a signing daemon started with [`serve`], the key never leaves the daemon.
+pub struct RemoteSigner {
+    connection: Mutex<BufReader<UnixStream>>,
+    address: Address,
+}
+
+impl RemoteSigner {
+    /// Connect to the daemon listening on the socket and fetch the address of its key.
+    pub async fn connect<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let stream = UnixStream::connect(path).await?;
+        let mut connection = BufReader::new(stream);
+        let address = match request(&mut connection, &Request::Address).await? {
+            Response::Address { address } => address,
+            response => anyhow::bail!("Unexpected remote signer response: {response:?}"),
+        };
+        Ok(RemoteSigner {
+            connection: Mutex::new(connection),
+            address,
+        })
+    }
+
+    pub fn address(&self) -> Address {
+        self.address.clone()
+    }
+}
+
+async fn request(connection: &mut BufReader<UnixStream>, request: &Request) -> Result<Response> {
+    let mut json = serde_json::to_vec(request)?;
+    json.push(b'\n');
+    connection.get_mut().write_all(&json).await?;
+
+    let mut line = String::new();
+    if connection.read_line(&mut line).await? == 0 {
+        anyhow::bail!("Remote signer closed the connection");
+    }
+    match serde_json::from_str(&line)? {
+        Response::Error { message } => anyhow::bail!("Remote signer error: {message}"),
+        response => Ok(response),
+    }
+}
+
+impl AsyncSigner for RemoteSigner {
+    async fn sign_hash(&self, hash: &B256) -> Result<Signature> {
+        let mut connection = self.connection.lock().await;
+        match request(&mut connection, &Request::SignHash { hash: *hash }).await? {
+            Response::Signature { signature } => Ok(signature),
+            response => anyhow::bail!("Unexpected remote signer response: {response:?}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::remote::{RemoteSigner, bind, serve};
+    use crate::signer::{AsyncSigner, Signer};
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::UnixStream;
+
+    fn socket_path() -> PathBuf {
+        let id: u64 = rand::random();
+        std::env::temp_dir().join(format!("bytelink-signer-{id:x}.sock"))
+    }
+
+    #[tokio::test]
+    async fn sign_remotely() {
+        let path = socket_path();
+        let signer = Arc::new(Signer::random());
+        let expected = signer.address();
+        let daemon = tokio::spawn(serve(bind(&path).unwrap(), signer));
+
+        let remote = RemoteSigner::connect(&path).await.unwrap();
+        assert_eq!(remote.address(), expected);
+
+        for msg in [b"hello".as_slice(), b"world"] {
+            let signature = remote.eip191_sign_msg_async(msg).await.unwrap();
+            assert_eq!(signature.recover_address_from_msg(msg).unwrap(), expected);
+        }
+
+        daemon.abort();
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn bind_owner_only() {
+        let path = socket_path();
+        let _listener = bind(&path).unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // the private directory is gone and the path is taken
+        let dir = path.parent().unwrap();
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let leftover = std::fs::read_dir(dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .any(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(&format!(".{name}."))
+            });
+        assert!(!leftover);
+        assert!(bind(&path).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reject_invalid_request() {
+        let path = socket_path();
+        let daemon = tokio::spawn(serve(bind(&path).unwrap(), Arc::new(Signer::random())));
+
+        let mut stream = BufReader::new(UnixStream::connect(&path).await.unwrap());
+        stream
+            .get_mut()
+            .write_all(b"{\"method\":\"steal_key\"}\n")
+            .await
+            .unwrap();
+        let mut line = String::new();
+        stream.read_line(&mut line).await.unwrap();
+        assert!(line.contains("\"result\":\"error\""));
+
+        daemon.abort();
+        std::fs::remove_file(path).unwrap();
+    }
+}
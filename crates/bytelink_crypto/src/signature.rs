@@ -1,10 +1,22 @@
 use crate::address::Address;
+use crate::constant::SECP256K1N_ORDER;
 use crate::eip191;
-use alloy_primitives::U256;
+use crate::eip712::{self, Eip712Domain, TypedData};
+use alloy_primitives::{U256, hex};
 use anyhow::Result;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::Formatter;
+use std::str::FromStr;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[repr(transparent)]
-pub struct Signature(pub(crate) alloy_primitives::Signature);
+pub struct Signature(alloy_primitives::Signature);
+
+impl From<alloy_primitives::Signature> for Signature {
+    fn from(sig: alloy_primitives::Signature) -> Self {
+        Signature(sig)
+    }
+}
 
 impl Signature {
     pub fn r(&self) -> U256 {
@@ -19,6 +31,23 @@ impl Signature {
         self.0.v()
     }
 
+    /// Whether `s` is in the lower half of the curve order, as described in
+    /// [BIP-62](https://github.com/bitcoin/bips/blob/master/bip-0062.mediawiki).
+    /// For every valid signature `(r, s, v)`, `(r, n - s, !v)` is valid as well, only the low-s one is canonical.
+    pub fn is_low_s(&self) -> bool {
+        self.s() <= SECP256K1N_ORDER >> 1
+    }
+
+    /// Return the low-s twin of the signature, `None` if it is already canonical.
+    pub fn normalize_s(&self) -> Option<Self> {
+        self.0.normalize_s().map(Signature)
+    }
+
+    /// Return the canonical low-s form of the signature.
+    pub fn normalized_s(self) -> Self {
+        self.normalize_s().unwrap_or(self)
+    }
+
     /// Serialize the signature into a 65-byte array `[r (32 bytes) | s (32 bytes) | v (1 byte)]`.
     /// where `v` follows the legacy format of [EIP-155](https://eips.ethereum.org/EIPS/eip-155)
     /// which is `{0, 1} + 27` depending on the parity of the y-coordinate of the recovery point.
@@ -26,6 +55,37 @@ impl Signature {
         self.0.as_bytes()
     }
 
+    /// Parse a signature from the 65-byte format produced by [`as_bytes`][Signature::as_bytes].
+    /// Only the legacy `v` values `27` and `28` are accepted, so a signature has a single encoding.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let Ok(bytes) = <&[u8; 65]>::try_from(bytes) else {
+            anyhow::bail!(
+                "Invalid signature length: expected 65 bytes, got {}",
+                bytes.len()
+            );
+        };
+
+        let parity = match bytes[64] {
+            27 => false,
+            28 => true,
+            v => anyhow::bail!("Invalid signature v value: expected 27 or 28, got {v}"),
+        };
+
+        let inner = alloy_primitives::Signature::from_bytes_and_parity(&bytes[..64], parity);
+        Ok(Signature(inner))
+    }
+
+    /// Parse a signature from the hex string of its 65-byte format, the "0x" prefix is optional.
+    pub fn from_hex<S: AsRef<str>>(hex: S) -> Result<Self> {
+        let bytes = hex::decode(hex.as_ref().trim())?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// return the "0x" prefixed hex string of the 65-byte format
+    pub fn to_hex(&self) -> String {
+        hex::encode_prefixed(self.as_bytes())
+    }
+
     /// Recover the address from the payload message that use [EIP-191](https://eips.ethereum.org/EIPS/eip-191) formatting.
     /// Where the prefix is the bytelink specific [`EIP191_PREFIX`][crate::constant::EIP191_PREFIX].
     pub fn recover_address_from_msg(&self, msg: &[u8]) -> Result<Address> {
@@ -33,12 +93,62 @@ impl Signature {
         let address = self.0.recover_address_from_prehash(&digest)?;
         Ok(address.into())
     }
+
+    /// Recover the address that signed the [EIP-712](https://eips.ethereum.org/EIPS/eip-712)
+    /// typed data under the given domain.
+    pub fn recover_address_from_typed_data<T: TypedData>(
+        &self,
+        domain: &Eip712Domain,
+        data: &T,
+    ) -> Result<Address> {
+        let digest = eip712::eip712_hash(domain, data);
+        let address = self.0.recover_address_from_prehash(&digest)?;
+        Ok(address.into())
+    }
+}
+
+impl std::fmt::Display for Signature {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl FromStr for Signature {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::from_hex(s)
+    }
+}
+
+/// Signatures are (de)serialized as the hex string of their 65-byte format.
+impl Serialize for Signature {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Signature {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        Signature::from_hex(hex).map_err(serde::de::Error::custom)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::constant::SECP256K1N_ORDER;
+    use crate::signature::Signature;
     use crate::signer::{Signer, SignerSync};
 
+    /// The high-s twin `(r, n - s, !v)` of a signature.
+    fn flip_s(signature: &Signature) -> Signature {
+        let mut bytes = signature.as_bytes();
+        bytes[32..64].copy_from_slice(&(SECP256K1N_ORDER - signature.s()).to_be_bytes::<32>());
+        bytes[64] = if signature.v() { 27 } else { 28 };
+        Signature::from_bytes(&bytes).unwrap()
+    }
+
     #[test]
     fn sign_and_recover() {
         let signer = Signer::random();
@@ -51,4 +161,88 @@ mod tests {
 
         assert_eq!(recovered_address, signer_address);
     }
+
+    #[test]
+    fn bytes_roundtrip() {
+        let signature = Signer::random().eip191_sign_msg(b"bytes").unwrap();
+        let bytes = signature.as_bytes();
+        assert_eq!(Signature::from_bytes(&bytes).unwrap(), signature);
+    }
+
+    #[test]
+    fn hex_roundtrip() {
+        let signature = Signer::random().eip191_sign_msg(b"hex").unwrap();
+        let hex = signature.to_hex();
+        assert!(hex.starts_with("0x"));
+        assert_eq!(hex.len(), 2 + 65 * 2);
+        assert_eq!(hex.parse::<Signature>().unwrap(), signature);
+        assert_eq!(Signature::from_hex(&hex[2..]).unwrap(), signature);
+        assert_eq!(signature.to_string(), hex);
+    }
+
+    #[test]
+    fn serde_roundtrip() {
+        let signature = Signer::random().eip191_sign_msg(b"serde").unwrap();
+        let json = serde_json::to_string(&signature).unwrap();
+        assert_eq!(json, format!("\"{}\"", signature.to_hex()));
+        let decoded: Signature = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, signature);
+    }
+
+    #[test]
+    fn invalid_length() {
+        let bytes = Signer::random().eip191_sign_msg(b"len").unwrap().as_bytes();
+        assert!(Signature::from_bytes(&[]).is_err());
+        assert!(Signature::from_bytes(&bytes[..64]).is_err());
+        assert!(Signature::from_bytes(&[bytes.as_slice(), &[0]].concat()).is_err());
+        assert!(Signature::from_hex("0x1234").is_err());
+        assert!(serde_json::from_str::<Signature>("\"0x1234\"").is_err());
+    }
+
+    #[test]
+    fn invalid_v() {
+        let mut bytes = Signer::random().eip191_sign_msg(b"v").unwrap().as_bytes();
+        for v in [0, 1, 26, 29, 35, 36, 255] {
+            bytes[64] = v;
+            assert!(Signature::from_bytes(&bytes).is_err(), "v = {v}");
+        }
+    }
+
+    #[test]
+    fn invalid_hex() {
+        assert!(Signature::from_hex("0xzz").is_err());
+        assert!(Signature::from_hex("not a signature").is_err());
+    }
+
+    #[test]
+    fn signer_produces_low_s() {
+        let signer = Signer::random();
+        for i in 0..32 {
+            let signature = signer
+                .eip191_sign_msg(format!("msg-{i}").as_bytes())
+                .unwrap();
+            assert!(signature.is_low_s());
+            assert!(signature.normalize_s().is_none());
+        }
+    }
+
+    #[test]
+    fn normalize_high_s() {
+        let signer = Signer::random();
+        let message = b"malleable";
+        let signature = signer.eip191_sign_msg(message).unwrap();
+        let twin = flip_s(&signature);
+
+        // the high-s twin is a different encoding of a valid signature
+        assert!(!twin.is_low_s());
+        assert_ne!(twin.as_bytes(), signature.as_bytes());
+        assert_eq!(
+            twin.recover_address_from_msg(message).unwrap(),
+            signer.address()
+        );
+
+        assert_eq!(twin.normalize_s(), Some(signature));
+        assert_eq!(twin.normalized_s(), signature);
+        assert_eq!(signature.normalized_s(), signature);
+    }
 }
@@ -1,5 +1,6 @@
 use crate::address::Address;
 use crate::eip191::eip191_hash;
+use crate::eip712::{Eip712Domain, TypedData, eip712_hash};
 use crate::signature::Signature;
 use alloy_primitives::B256;
 use anyhow::Result;
@@ -14,12 +15,50 @@ pub trait SignerSync {
         let digest = eip191_hash(msg);
         self.sign_hash_sync(&digest)
     }
+
+    /// Signs the given typed data using [EIP-712](https://eips.ethereum.org/EIPS/eip-712)
+    /// under the given domain, which should carry the bytelink chain id.
+    fn eip712_sign<T: TypedData>(&self, domain: &Eip712Domain, data: &T) -> Result<Signature> {
+        let digest = eip712_hash(domain, data);
+        self.sign_hash_sync(&digest)
+    }
+}
+
+/// The asynchronous counterpart of [`SignerSync`], for signers that do not hold the key
+/// in-process, such as the [`RemoteSigner`][crate::remote::RemoteSigner].
+/// Every [`SignerSync`] is an `AsyncSigner` as well.
+pub trait AsyncSigner: Sync {
+    /// Signs the given hash.
+    fn sign_hash(&self, hash: &B256) -> impl Future<Output = Result<Signature>> + Send;
+
+    /// Asynchronous [`SignerSync::eip191_sign_msg`].
+    fn eip191_sign_msg_async(&self, msg: &[u8]) -> impl Future<Output = Result<Signature>> + Send {
+        let digest = eip191_hash(msg);
+        async move { self.sign_hash(&digest).await }
+    }
+
+    /// Asynchronous [`SignerSync::eip712_sign`].
+    fn eip712_sign_async<T: TypedData>(
+        &self,
+        domain: &Eip712Domain,
+        data: &T,
+    ) -> impl Future<Output = Result<Signature>> + Send {
+        let digest = eip712_hash(domain, data);
+        async move { self.sign_hash(&digest).await }
+    }
+}
+
+impl<T: SignerSync + Sync> AsyncSigner for T {
+    fn sign_hash(&self, hash: &B256) -> impl Future<Output = Result<Signature>> + Send {
+        let signature = self.sign_hash_sync(hash);
+        async move { signature }
+    }
 }
 
 impl<T: alloy_signer::SignerSync> SignerSync for T {
     fn sign_hash_sync(&self, hash: &B256) -> Result<Signature> {
         let sig = self.sign_hash_sync(hash)?;
-        Ok(Signature(sig))
+        Ok(Signature::from(sig))
     }
 }
 
@@ -37,8 +76,27 @@ impl Signer {
         Self(alloy_signer_local::PrivateKeySigner::random())
     }
 
+    /// Create a signer from the 32-byte big-endian secret key.
+    pub fn from_slice(bytes: &[u8]) -> Result<Self> {
+        let inner = alloy_signer_local::PrivateKeySigner::from_slice(bytes)?;
+        Ok(Self(inner))
+    }
+
     pub fn address(&self) -> Address {
         let address = self.0.address();
         Address::from(address)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::signer::{AsyncSigner, Signer};
+
+    #[tokio::test]
+    async fn local_signer_is_async() {
+        let signer = Signer::random();
+        let signature = signer.eip191_sign_msg_async(b"hello").await.unwrap();
+        let recovered = signature.recover_address_from_msg(b"hello").unwrap();
+        assert_eq!(recovered, signer.address());
+    }
+}
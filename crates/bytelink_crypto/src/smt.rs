@@ -0,0 +1,294 @@
+use alloy_primitives::{B256, keccak256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Depth of the tree, one level per bit of a 256-bit key.
+pub const DEPTH: usize = 256;
+
+const LEAF_TAG: u8 = 0x00;
+const NODE_TAG: u8 = 0x01;
+
+/// Hash of an empty subtree at every height, `EMPTY[0]` being an empty leaf.
+static EMPTY: LazyLock<[B256; DEPTH + 1]> = LazyLock::new(|| {
+    let mut empty = [B256::ZERO; DEPTH + 1];
+    for height in 0..DEPTH {
+        empty[height + 1] = node_hash(&empty[height], &empty[height]);
+    }
+    empty
+});
+
+/// Hash a leaf of the tree, which is `keccak256(0x00 | key | value)`.
+fn leaf_hash(key: &B256, value: &B256) -> B256 {
+    let mut buf = [0u8; 65];
+    buf[0] = LEAF_TAG;
+    buf[1..33].copy_from_slice(key.as_slice());
+    buf[33..].copy_from_slice(value.as_slice());
+    keccak256(buf)
+}
+
+/// Hash two children of the tree, which is `keccak256(0x01 | left | right)`.
+fn node_hash(left: &B256, right: &B256) -> B256 {
+    let mut buf = [0u8; 65];
+    buf[0] = NODE_TAG;
+    buf[1..33].copy_from_slice(left.as_slice());
+    buf[33..].copy_from_slice(right.as_slice());
+    keccak256(buf)
+}
+
+/// The bit of `key` deciding whether the node at `height` is a left (`false`) or right (`true`) child.
+fn bit(key: &B256, height: usize) -> bool {
+    (key[31 - height / 8] >> (height % 8)) & 1 == 1
+}
+
+/// The key of the subtree at `height` containing `key`, i.e. `key` with its lowest `height` bits cleared.
+fn prefix(key: &B256, height: usize) -> B256 {
+    let height = height.min(DEPTH);
+    let mut prefix = *key;
+    let (bytes, bits) = (height / 8, height % 8);
+    prefix[32 - bytes..].fill(0);
+    if bits > 0 {
+        prefix[31 - bytes] &= 0xff << bits;
+    }
+    prefix
+}
+
+/// Flip the bit of `key` at `height`, which gives the key of the sibling subtree.
+fn flip(mut key: B256, height: usize) -> B256 {
+    key[31 - height / 8] ^= 1 << (height % 8);
+    key
+}
+
+/// A sparse Merkle tree of depth 256 using Keccak-256, mapping 256-bit keys to 256-bit values.
+///
+/// Only non-empty subtrees are stored, the others are known in advance, so updates
+/// are incremental and cost [`DEPTH`] hashes each. Values are usually the hash of the
+/// actual record, the tree being a commitment to the records rather than their storage.
+#[derive(Debug, Clone, Default)]
+pub struct SparseMerkleTree {
+    /// non-empty nodes indexed by `(height, prefix)`
+    nodes: HashMap<(usize, B256), B256>,
+}
+
+impl SparseMerkleTree {
+    pub fn new() -> Self {
+        SparseMerkleTree {
+            nodes: HashMap::new(),
+        }
+    }
+
+    /// The root of the tree, which commits to every key-value pair.
+    pub fn root(&self) -> B256 {
+        self.node(DEPTH, &B256::ZERO)
+    }
+
+    /// The root of a tree without any value.
+    pub fn empty_root() -> B256 {
+        EMPTY[DEPTH]
+    }
+
+    fn node(&self, height: usize, prefix: &B256) -> B256 {
+        self.nodes
+            .get(&(height, *prefix))
+            .copied()
+            .unwrap_or(EMPTY[height])
+    }
+
+    fn set_node(&mut self, height: usize, prefix: B256, hash: B256) {
+        if hash == EMPTY[height] {
+            self.nodes.remove(&(height, prefix));
+        } else {
+            self.nodes.insert((height, prefix), hash);
+        }
+    }
+
+    /// Set the value of `key`, `None` removes it. Returns the new root.
+    pub fn update(&mut self, key: &B256, value: Option<&B256>) -> B256 {
+        let mut hash = match value {
+            Some(value) => leaf_hash(key, value),
+            None => EMPTY[0],
+        };
+        self.set_node(0, *key, hash);
+
+        for height in 0..DEPTH {
+            let sibling = self.node(height, &flip(prefix(key, height), height));
+            hash = if hash == EMPTY[height] && sibling == EMPTY[height] {
+                EMPTY[height + 1]
+            } else if bit(key, height) {
+                node_hash(&sibling, &hash)
+            } else {
+                node_hash(&hash, &sibling)
+            };
+            self.set_node(height + 1, prefix(key, height + 1), hash);
+        }
+
+        hash
+    }
+
+    pub fn insert(&mut self, key: &B256, value: &B256) -> B256 {
+        self.update(key, Some(value))
+    }
+
+    pub fn remove(&mut self, key: &B256) -> B256 {
+        self.update(key, None)
+    }
+
+    /// Create a proof of the current value of `key`, which can also prove its absence.
+    pub fn proof(&self, key: &B256) -> SparseMerkleProof {
+        let mut bitmap = B256::ZERO;
+        let mut siblings = Vec::new();
+        for height in 0..DEPTH {
+            let sibling = self.node(height, &flip(prefix(key, height), height));
+            // empty siblings are known by the verifier, only flag them in the bitmap
+            if sibling != EMPTY[height] {
+                bitmap = flip(bitmap, height);
+                siblings.push(sibling);
+            }
+        }
+        SparseMerkleProof { bitmap, siblings }
+    }
+}
+
+/// A proof that a key of a [`SparseMerkleTree`] maps to a value, or to nothing.
+///
+/// Empty siblings are omitted, bit `h` of the `bitmap` tells whether the sibling at height `h`
+/// is present in `siblings`, which are ordered from the leaf up to the root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SparseMerkleProof {
+    pub bitmap: B256,
+    pub siblings: Vec<B256>,
+}
+
+impl SparseMerkleProof {
+    /// Verify that `key` maps to `value` (or is absent if `None`) in the tree with the given `root`.
+    pub fn verify(&self, root: &B256, key: &B256, value: Option<&B256>) -> bool {
+        let mut siblings = self.siblings.iter();
+        let mut hash = match value {
+            Some(value) => leaf_hash(key, value),
+            None => EMPTY[0],
+        };
+
+        for height in 0..DEPTH {
+            let sibling = if bit(&self.bitmap, height) {
+                match siblings.next() {
+                    Some(sibling) => *sibling,
+                    None => return false,
+                }
+            } else {
+                EMPTY[height]
+            };
+            hash = if hash == EMPTY[height] && sibling == EMPTY[height] {
+                EMPTY[height + 1]
+            } else if bit(key, height) {
+                node_hash(&sibling, &hash)
+            } else {
+                node_hash(&hash, &sibling)
+            };
+        }
+
+        siblings.next().is_none() && hash == *root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::smt::SparseMerkleTree;
+    use alloy_primitives::{B256, keccak256};
+
+    fn key(i: u64) -> B256 {
+        keccak256(i.to_be_bytes())
+    }
+
+    fn value(i: u64) -> B256 {
+        keccak256(format!("value-{i}"))
+    }
+
+    #[test]
+    fn empty_tree() {
+        let tree = SparseMerkleTree::new();
+        assert_eq!(tree.root(), SparseMerkleTree::empty_root());
+        assert!(tree.proof(&key(0)).verify(&tree.root(), &key(0), None));
+    }
+
+    #[test]
+    fn root_is_independent_of_insertion_order() {
+        let mut forward = SparseMerkleTree::new();
+        let mut backward = SparseMerkleTree::new();
+        for i in 0..32 {
+            forward.insert(&key(i), &value(i));
+            backward.insert(&key(31 - i), &value(31 - i));
+        }
+        assert_eq!(forward.root(), backward.root());
+    }
+
+    #[test]
+    fn remove_restores_previous_root() {
+        let mut tree = SparseMerkleTree::new();
+        tree.insert(&key(0), &value(0));
+        let root = tree.root();
+
+        tree.insert(&key(1), &value(1));
+        assert_ne!(tree.root(), root);
+
+        tree.remove(&key(1));
+        assert_eq!(tree.root(), root);
+
+        tree.remove(&key(0));
+        assert_eq!(tree.root(), SparseMerkleTree::empty_root());
+    }
+
+    #[test]
+    fn update_changes_root() {
+        let mut tree = SparseMerkleTree::new();
+        let before = tree.insert(&key(0), &value(0));
+        let after = tree.insert(&key(0), &value(1));
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn prove_membership() {
+        let mut tree = SparseMerkleTree::new();
+        for i in 0..16 {
+            tree.insert(&key(i), &value(i));
+        }
+        let root = tree.root();
+        for i in 0..16 {
+            let proof = tree.proof(&key(i));
+            assert!(proof.verify(&root, &key(i), Some(&value(i))));
+            assert!(!proof.verify(&root, &key(i), Some(&value(i + 1))));
+            assert!(!proof.verify(&root, &key(i), None));
+        }
+    }
+
+    #[test]
+    fn prove_non_membership() {
+        let mut tree = SparseMerkleTree::new();
+        for i in 0..16 {
+            tree.insert(&key(i), &value(i));
+        }
+        let root = tree.root();
+        let proof = tree.proof(&key(100));
+        assert!(proof.verify(&root, &key(100), None));
+        assert!(!proof.verify(&root, &key(100), Some(&value(100))));
+    }
+
+    #[test]
+    fn reject_tampered_proof() {
+        let mut tree = SparseMerkleTree::new();
+        for i in 0..4 {
+            tree.insert(&key(i), &value(i));
+        }
+        let root = tree.root();
+
+        let mut proof = tree.proof(&key(0));
+        proof.siblings[0] = value(42);
+        assert!(!proof.verify(&root, &key(0), Some(&value(0))));
+
+        let mut proof = tree.proof(&key(0));
+        proof.siblings.push(value(42));
+        assert!(!proof.verify(&root, &key(0), Some(&value(0))));
+
+        let proof = tree.proof(&key(0));
+        assert!(!proof.verify(&root, &key(1), Some(&value(0))));
+    }
+}
@@ -1,33 +1,409 @@
 use crate::database::account::Account;
+use crate::database::htlc::Htlc;
+use crate::database::token::Token;
+use crate::database::trie::{AccountProof, StateKey, StateTrie};
 use crate::genesis::Genesis;
+use alloy_primitives::B256;
 use anyhow::Result;
 use bytelink_crypto::address::Address;
-use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
 use dashmap::mapref::one::Ref;
+use dashmap::{DashMap, DashSet};
+use std::sync::RwLock;
 
 pub mod account;
+pub mod history;
+pub mod htlc;
+pub mod multisig;
+pub mod timelock;
+pub mod token;
 pub mod transaction;
+pub mod trie;
 
 /// The in-memory, concurrent safe database for blockchain accounts.
+///
+/// The whole state is committed to the state root: the accounts, the code and storage of the
+/// contracts, the tokens and the pending HTLCs, see [`StateTrie`].
 pub struct Database {
     genesis: Genesis,
     accounts: DashMap<Address, Account>,
+    /// commitment to the state as of the last [`commit`][Database::commit]
+    trie: RwLock<StateTrie>,
+    /// state modified since the last commit
+    dirty: DashSet<StateKey>,
+    /// code of the contract accounts, run by the [`vm`][crate::vm]
+    code: DashMap<Address, Vec<u8>>,
+    /// storage words of the contract accounts
+    storage: DashMap<(Address, u64), u64>,
+    /// the native [`Token`]s, by id
+    tokens: DashMap<Address, Token>,
+    /// token balances, keyed by `(token id, holder)`
+    token_balances: DashMap<(Address, Address), u64>,
+    /// the tokens every holder has a balance in, indexing `token_balances` by holder
+    holdings: DashMap<Address, DashSet<Address>>,
+    /// token allowances, keyed by `(token id, owner, spender)`
+    allowances: DashMap<(Address, Address, Address), u64>,
+    /// the pending [`Htlc`]s, by id, removed once claimed or refunded
+    htlcs: DashMap<Address, Htlc>,
 }
 
 impl Database {
     pub fn new(genesis: Genesis) -> Result<Database> {
         let accounts = DashMap::new();
+        let mut trie = StateTrie::new();
 
         for (addr, balance) in &genesis.balances {
             let address = Address::parse_checksummed(addr)?;
             let account = Account::new(address.clone(), *balance);
+            trie.update(&address, Some(&account));
             accounts.insert(address, account);
         }
 
-        Ok(Database { genesis, accounts })
+        Ok(Database {
+            genesis,
+            accounts,
+            trie: RwLock::new(trie),
+            dirty: DashSet::new(),
+            code: DashMap::new(),
+            storage: DashMap::new(),
+            tokens: DashMap::new(),
+            token_balances: DashMap::new(),
+            holdings: DashMap::new(),
+            allowances: DashMap::new(),
+            htlcs: DashMap::new(),
+        })
+    }
+
+    pub fn genesis(&self) -> &Genesis {
+        &self.genesis
     }
 
     pub fn query<T: AsRef<Address>>(&self, address: T) -> Option<Ref<'_, Address, Account>> {
         self.accounts.get(address.as_ref())
     }
+
+    /// Insert or replace an account, the state root is updated on the next [`commit`][Database::commit].
+    pub fn upsert(&self, account: Account) {
+        self.dirty
+            .insert(StateKey::Account(account.address().clone()));
+        self.accounts.insert(account.address().clone(), account);
+    }
+
+    /// The code of a contract account, `None` for an account held by a key.
+    pub fn code(&self, address: &Address) -> Option<Vec<u8>> {
+        self.code.get(address).map(|code| code.clone())
+    }
+
+    /// Deploy a contract, contract code cannot be changed afterwards.
+    pub fn set_code(&self, address: Address, code: Vec<u8>) -> Result<()> {
+        match self.code.entry(address) {
+            Entry::Occupied(entry) => {
+                anyhow::bail!("Contract already exists: {}", entry.key().checksummed())
+            }
+            Entry::Vacant(entry) => {
+                self.dirty.insert(StateKey::Contract(entry.key().clone()));
+                entry.insert(code);
+                Ok(())
+            }
+        }
+    }
+
+    /// A storage word of a contract account, zero if never written.
+    pub fn storage(&self, address: &Address, key: u64) -> u64 {
+        self.storage
+            .get(&(address.clone(), key))
+            .map_or(0, |value| *value)
+    }
+
+    pub fn set_storage(&self, address: Address, key: u64, value: u64) {
+        self.dirty.insert(StateKey::Storage(address.clone(), key));
+        self.dirty.insert(StateKey::Contract(address.clone()));
+        self.storage.insert((address, key), value);
+    }
+
+    pub fn token(&self, id: &Address) -> Option<Token> {
+        self.tokens.get(id).map(|token| token.clone())
+    }
+
+    /// Register a new token, its supply must be credited to the issuer separately.
+    pub fn create_token(&self, id: Address, token: Token) -> Result<()> {
+        match self.tokens.entry(id) {
+            Entry::Occupied(entry) => {
+                anyhow::bail!("Token already exists: {}", entry.key().checksummed())
+            }
+            Entry::Vacant(entry) => {
+                self.dirty.insert(StateKey::Token(entry.key().clone()));
+                entry.insert(token);
+                Ok(())
+            }
+        }
+    }
+
+    /// The balance of `holder` in the token, zero if it never held any.
+    pub fn token_balance(&self, token: &Address, holder: &Address) -> u64 {
+        self.token_balances
+            .get(&(token.clone(), holder.clone()))
+            .map_or(0, |balance| *balance)
+    }
+
+    pub fn set_token_balance(&self, token: Address, holder: Address, balance: u64) {
+        self.dirty
+            .insert(StateKey::TokenBalance(token.clone(), holder.clone()));
+        self.holdings
+            .entry(holder.clone())
+            .or_default()
+            .insert(token.clone());
+        self.token_balances.insert((token, holder), balance);
+    }
+
+    /// Every token balance of `holder`, by token id.
+    pub fn token_balances(&self, holder: &Address) -> Vec<(Address, u64)> {
+        let Some(tokens) = self.holdings.get(holder) else {
+            return vec![];
+        };
+        let mut balances: Vec<_> = tokens
+            .iter()
+            .map(|token| (token.clone(), self.token_balance(&token, holder)))
+            .collect();
+        balances.sort_unstable_by_key(|(token, _)| *token.as_bytes());
+        balances
+    }
+
+    /// How much of the token of `owner` the `spender` may still transfer.
+    pub fn allowance(&self, token: &Address, owner: &Address, spender: &Address) -> u64 {
+        self.allowances
+            .get(&(token.clone(), owner.clone(), spender.clone()))
+            .map_or(0, |allowance| *allowance)
+    }
+
+    pub fn set_allowance(&self, token: Address, owner: Address, spender: Address, amount: u64) {
+        self.dirty.insert(StateKey::Allowance(
+            token.clone(),
+            owner.clone(),
+            spender.clone(),
+        ));
+        self.allowances.insert((token, owner, spender), amount);
+    }
+
+    /// A pending HTLC, `None` if it never existed or was already claimed or refunded.
+    pub fn htlc(&self, id: &Address) -> Option<Htlc> {
+        self.htlcs.get(id).map(|htlc| htlc.clone())
+    }
+
+    /// Hold the funds of a new HTLC, they must be debited from the sender separately.
+    pub fn lock_htlc(&self, id: Address, htlc: Htlc) -> Result<()> {
+        match self.htlcs.entry(id) {
+            Entry::Occupied(entry) => {
+                anyhow::bail!("HTLC already exists: {}", entry.key().checksummed())
+            }
+            Entry::Vacant(entry) => {
+                self.dirty.insert(StateKey::Htlc(entry.key().clone()));
+                entry.insert(htlc);
+                Ok(())
+            }
+        }
+    }
+
+    /// Release a claimed or refunded HTLC, its funds must be credited separately.
+    pub fn settle_htlc(&self, id: &Address) -> Result<Htlc> {
+        let (_, htlc) = self
+            .htlcs
+            .remove(id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown HTLC: {}", id.checksummed()))?;
+        self.dirty.insert(StateKey::Htlc(id.clone()));
+        Ok(htlc)
+    }
+
+    /// Fold the state modified since the last commit into the state trie,
+    /// usually once a block has been applied. Returns the new state root.
+    pub fn commit(&self) -> B256 {
+        let mut trie = self.trie.write().expect("state trie lock poisoned");
+        let dirty: Vec<StateKey> = self.dirty.iter().map(|key| key.clone()).collect();
+        let mut contracts = Vec::new();
+        for key in dirty {
+            // unmark first, so a concurrent update is picked up by the next commit
+            self.dirty.remove(&key);
+            match key {
+                StateKey::Account(address) => {
+                    let account = self.accounts.get(&address);
+                    trie.update(&address, account.as_deref());
+                }
+                StateKey::Storage(address, slot) => {
+                    trie.update_storage(&address, slot, self.storage(&address, slot));
+                }
+                // the leaf of a contract depends on its storage root, updated last
+                StateKey::Contract(address) => contracts.push(address),
+                StateKey::Token(id) => {
+                    trie.update_token(&id, self.tokens.get(&id).as_deref());
+                }
+                StateKey::TokenBalance(token, holder) => {
+                    let balance = self.token_balance(&token, &holder);
+                    trie.update_token_balance(&token, &holder, balance);
+                }
+                StateKey::Allowance(token, owner, spender) => {
+                    let amount = self.allowance(&token, &owner, &spender);
+                    trie.update_allowance(&token, &owner, &spender, amount);
+                }
+                StateKey::Htlc(id) => {
+                    trie.update_htlc(&id, self.htlcs.get(&id).as_deref());
+                }
+            }
+        }
+        for address in contracts {
+            let code = self.code.get(&address);
+            trie.update_contract(&address, code.as_deref().map(Vec::as_slice));
+        }
+        trie.root()
+    }
+
+    /// The state root as of the last [`commit`][Database::commit].
+    pub fn state_root(&self) -> B256 {
+        self.trie.read().expect("state trie lock poisoned").root()
+    }
+
+    /// Prove the state of `address` against the current [`state_root`][Database::state_root].
+    pub fn prove(&self, address: &Address) -> Result<AccountProof> {
+        let trie = self.trie.read().expect("state trie lock poisoned");
+        if self.dirty.contains(&StateKey::Account(address.clone())) {
+            anyhow::bail!("Account {} has uncommitted changes", address.checksummed());
+        }
+        let account = self.accounts.get(address).map(|account| account.clone());
+        Ok(trie.prove(address, account))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::Database;
+    use crate::database::account::Account;
+    use crate::database::htlc::Htlc;
+    use crate::database::timelock::TimeLock;
+    use crate::database::token::Token;
+    use crate::genesis::Genesis;
+    use alloy_primitives::keccak256;
+    use bytelink_crypto::address::Address;
+    use rand::rng;
+
+    fn database() -> Database {
+        let genesis = Genesis::load("../../assets/genesis.json").unwrap();
+        Database::new(genesis).unwrap()
+    }
+
+    #[test]
+    fn genesis_state_root_is_deterministic() {
+        assert_eq!(database().state_root(), database().state_root());
+    }
+
+    #[test]
+    fn query_by_address() {
+        let db = database();
+        let address: Address = "0xf01813e4b85e178a83e29b8e7bf26bd830a25f32"
+            .parse()
+            .unwrap();
+        assert_eq!(db.query(&address).unwrap().balance(), 1000000);
+        assert_eq!(db.query(address).unwrap().balance(), 1000000);
+        assert!(db.query(Address::random(rng())).is_none());
+    }
+
+    #[test]
+    fn commit_updates_state_root() {
+        let db = database();
+        let genesis_root = db.state_root();
+
+        let address = Address::random(rng());
+        db.upsert(Account::new(address.clone(), 42));
+        assert_eq!(db.state_root(), genesis_root);
+        assert!(db.prove(&address).is_err());
+
+        let root = db.commit();
+        assert_ne!(root, genesis_root);
+        assert_eq!(db.state_root(), root);
+
+        let proof = db.prove(&address).unwrap();
+        assert_eq!(proof.account.as_ref().map(Account::balance), Some(42));
+        assert!(proof.verify(&root).is_ok());
+        assert!(proof.verify(&genesis_root).is_err());
+    }
+
+    #[test]
+    fn incremental_root_matches_rebuilt_root() {
+        let db = database();
+        let address = Address::random(rng());
+        db.upsert(Account::new(address.clone(), 42));
+        let incremental = db.commit();
+
+        let mut genesis = Genesis::load("../../assets/genesis.json").unwrap();
+        genesis.balances.insert(address.checksummed(), 42);
+        let rebuilt = Database::new(genesis).unwrap().state_root();
+
+        assert_eq!(incremental, rebuilt);
+    }
+
+    #[test]
+    fn commit_token_state() {
+        let db = database();
+        let genesis_root = db.state_root();
+        let (token, alice, bob) = (
+            Address::random(rng()),
+            Address::random(rng()),
+            Address::random(rng()),
+        );
+
+        db.create_token(token.clone(), Token::new(alice.clone(), 100))
+            .unwrap();
+        db.set_token_balance(token.clone(), alice.clone(), 100);
+        let created = db.commit();
+        assert_ne!(created, genesis_root);
+
+        db.set_token_balance(token.clone(), alice.clone(), 60);
+        db.set_token_balance(token.clone(), bob.clone(), 40);
+        let transferred = db.commit();
+        assert_ne!(transferred, created);
+
+        db.set_allowance(token.clone(), alice.clone(), bob.clone(), 10);
+        assert_ne!(db.commit(), transferred);
+        db.set_allowance(token.clone(), alice.clone(), bob.clone(), 0);
+        assert_eq!(db.commit(), transferred);
+
+        assert_eq!(db.token_balances(&bob), vec![(token, 40)]);
+        assert!(db.token_balances(&Address::random(rng())).is_empty());
+    }
+
+    #[test]
+    fn commit_pending_htlcs() {
+        let db = database();
+        let genesis_root = db.state_root();
+
+        let id = Address::random(rng());
+        let htlc = Htlc {
+            sender: Address::random(rng()),
+            recipient: Address::random(rng()),
+            amount: 100,
+            hashlock: keccak256(b"secret"),
+            timeout: TimeLock::Height(10),
+        };
+        db.lock_htlc(id.clone(), htlc).unwrap();
+        assert_ne!(db.commit(), genesis_root);
+
+        db.settle_htlc(&id).unwrap();
+        assert_eq!(db.commit(), genesis_root);
+    }
+
+    #[test]
+    fn commit_contract_state() {
+        let db = database();
+        let genesis_root = db.state_root();
+
+        let contract = Address::random(rng());
+        db.set_code(contract.clone(), vec![0x00]).unwrap();
+        let deployed = db.commit();
+        assert_ne!(deployed, genesis_root);
+
+        db.set_storage(contract.clone(), 7, 42);
+        let written = db.commit();
+        assert_ne!(written, deployed);
+
+        db.set_storage(contract, 7, 0);
+        assert_eq!(db.commit(), deployed);
+    }
 }
@@ -1,5 +1,9 @@
+use alloy_primitives::{B256, keccak256};
+use anyhow::Result;
 use bytelink_crypto::address::Address;
+use serde::{Deserialize, Serialize};
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Account {
     address: Address,
     nonce: u64,
@@ -14,4 +18,59 @@ impl Account {
             balance,
         }
     }
+
+    pub fn address(&self) -> &Address {
+        &self.address
+    }
+
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    pub fn balance(&self) -> u64 {
+        self.balance
+    }
+
+    pub(crate) fn credit(&mut self, amount: u64) -> Result<()> {
+        self.balance = self
+            .balance
+            .checked_add(amount)
+            .ok_or_else(|| anyhow::anyhow!("Balance overflow: {}", self.address.checksummed()))?;
+        Ok(())
+    }
+
+    pub(crate) fn debit(&mut self, amount: u64) -> Result<()> {
+        self.balance = self.balance.checked_sub(amount).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Insufficient balance: {} holds {}, needs {amount}",
+                self.address.checksummed(),
+                self.balance
+            )
+        })?;
+        Ok(())
+    }
+
+    pub(crate) fn increment_nonce(&mut self) {
+        self.nonce += 1;
+    }
+
+    /// Encode the account into its canonical byte representation, integers are big-endian.
+    /// ```text
+    /// ┌──────────────┬───────────┬─────────────┐
+    /// │   address    │   nonce   │   balance   │
+    /// │  (20 bytes)  │ (8 bytes) │  (8 bytes)  │
+    /// └──────────────┴───────────┴─────────────┘
+    /// ```
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(20 + 8 + 8);
+        buf.extend_from_slice(self.address.as_bytes());
+        buf.extend_from_slice(&self.nonce.to_be_bytes());
+        buf.extend_from_slice(&self.balance.to_be_bytes());
+        buf
+    }
+
+    /// Keccak-256 hash of the [encoded][Account::encode] account, committed in the state trie.
+    pub fn hash(&self) -> B256 {
+        keccak256(self.encode())
+    }
 }
@@ -0,0 +1,308 @@
+use crate::database::transaction::{BlockTx, TxKind};
+use crate::state::Receipt;
+use crate::vm::contract_address;
+use alloy_primitives::B256;
+use anyhow::Result;
+use bytelink_crypto::address::Address;
+use dashmap::DashMap;
+use serde::Serialize;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The maximum number of records in a [`Page`].
+pub const MAX_PAGE_SIZE: usize = 100;
+
+/// A transaction involving an address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TxRecord {
+    /// hash of the signed transaction
+    pub hash: B256,
+    /// height of the block including it
+    pub height: u64,
+}
+
+/// A page of the history of an address, oldest first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Page {
+    pub records: Vec<TxRecord>,
+    /// number of records of the address
+    pub total: usize,
+    /// offset of the next page, `None` on the last one
+    pub next: Option<usize>,
+}
+
+/// An optional index of the transactions sent or received by every address, in chain order.
+///
+/// The index is not part of the state, it can be dropped and [`rebuild`][History::rebuild]
+/// from the chain at any time. An address is involved in a transaction if it is the sender,
+/// the recipient, the owner whose tokens are sent by a
+/// [`TokenTransferFrom`][TxKind::TokenTransferFrom], or the party an HTLC is settled to: its
+/// recipient on a [`HtlcClaim`][TxKind::HtlcClaim], its sender on a
+/// [`HtlcRefund`][TxKind::HtlcRefund]. Only the HTLCs whose lock did not revert are settled.
+#[derive(Default)]
+pub struct History {
+    records: DashMap<Address, Vec<TxRecord>>,
+    /// the `(sender, recipient)` of every HTLC locked so far, by id
+    htlcs: DashMap<Address, (Address, Address)>,
+    /// height of the last indexed block
+    height: AtomicU64,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index every transaction of the chain, given as the transactions of each block by height
+    /// with their receipts.
+    pub fn rebuild<'a, I, T>(blocks: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (u64, T)>,
+        T: IntoIterator<Item = (&'a BlockTx, &'a Receipt)>,
+    {
+        let history = Self::new();
+        for (height, txs) in blocks {
+            for (tx, receipt) in txs {
+                history.record(height, tx, receipt)?;
+            }
+        }
+        Ok(history)
+    }
+
+    /// Check that the block at `height` can be indexed, without indexing anything.
+    pub fn check(&self, height: u64) -> Result<()> {
+        let last = self.height.load(Ordering::SeqCst);
+        if height < last {
+            anyhow::bail!("Invalid history height: {height} is before the last block {last}");
+        }
+        Ok(())
+    }
+
+    /// Index a transaction included in the block at `height` with its receipt,
+    /// blocks must be indexed in order.
+    pub fn record(&self, height: u64, tx: &BlockTx, receipt: &Receipt) -> Result<()> {
+        self.height
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+                (height >= last).then_some(height)
+            })
+            .map_err(|last| {
+                anyhow::anyhow!("Invalid history height: {height} is before the last block {last}")
+            })?;
+
+        let record = TxRecord {
+            hash: tx.signed_tx().hash(),
+            height,
+        };
+        let transaction = tx.tx();
+        let mut involved = vec![tx.sender().clone(), transaction.to.clone()];
+        match &transaction.kind {
+            TxKind::TokenTransferFrom { from, .. } => involved.push(from.clone()),
+            TxKind::HtlcLock { .. } if receipt.revert.is_none() => {
+                let id = contract_address(tx.sender(), transaction.nonce);
+                let parties = (tx.sender().clone(), transaction.to.clone());
+                self.htlcs.insert(id, parties);
+            }
+            TxKind::HtlcClaim | TxKind::HtlcRefund => {
+                if let Some(parties) = self.htlcs.get(&transaction.to) {
+                    let (sender, recipient) = parties.value();
+                    let beneficiary = match transaction.kind {
+                        TxKind::HtlcClaim => recipient,
+                        _ => sender,
+                    };
+                    involved.push(beneficiary.clone());
+                }
+            }
+            _ => {}
+        }
+        involved.sort_unstable_by_key(|address| *address.as_bytes());
+        involved.dedup();
+
+        for address in involved {
+            self.records
+                .entry(address)
+                .or_default()
+                .push(record.clone());
+        }
+        Ok(())
+    }
+
+    /// Up to `limit` transactions involving `address`, starting at `offset`.
+    pub fn page(&self, address: &Address, offset: usize, limit: usize) -> Page {
+        let Some(records) = self.records.get(address) else {
+            return Page {
+                records: vec![],
+                total: 0,
+                next: None,
+            };
+        };
+        let limit = limit.min(MAX_PAGE_SIZE);
+        let page: Vec<_> = records.iter().skip(offset).take(limit).cloned().collect();
+        let end = offset.saturating_add(page.len());
+        Page {
+            records: page,
+            total: records.len(),
+            next: (end < records.len()).then_some(end),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::history::History;
+    use crate::database::timelock::TimeLock;
+    use crate::database::transaction::{BlockTx, Transaction, TxKind};
+    use crate::state::Receipt;
+    use crate::vm::contract_address;
+    use alloy_primitives::keccak256;
+    use bytelink_crypto::address::Address;
+    use bytelink_crypto::signer::Signer;
+    use rand::rng;
+
+    static APPLIED: Receipt = Receipt {
+        gas_used: 21,
+        revert: None,
+    };
+
+    /// The transactions of a block, all applied without reverting.
+    fn applied(txs: &[BlockTx]) -> impl Iterator<Item = (&BlockTx, &Receipt)> {
+        txs.iter().map(|tx| (tx, &APPLIED))
+    }
+
+    fn block_tx(signer: &Signer, nonce: u64, to: &Address, kind: TxKind) -> BlockTx {
+        let tx = Transaction {
+            kind,
+            ..Transaction::new(nonce, to.clone(), 1, 0)
+        };
+        BlockTx::new(tx.sign_by(signer).unwrap(), 1, 21)
+    }
+
+    #[test]
+    fn index_sent_and_received() {
+        let alice = Signer::random();
+        let bob = Address::random(rng());
+        let carol = Address::random(rng());
+        let txs = [
+            block_tx(&alice, 0, &bob, TxKind::Transfer),
+            block_tx(&alice, 1, &carol, TxKind::Transfer),
+        ];
+        let spend = TxKind::TokenTransferFrom {
+            token: Address::random(rng()),
+            from: bob.clone(),
+            amount: 1,
+        };
+        let later = [block_tx(&alice, 2, &carol, spend)];
+        let history = History::rebuild([(1, applied(&txs)), (2, applied(&later))]).unwrap();
+
+        let sent = history.page(&alice.address(), 0, 10);
+        assert_eq!(sent.total, 3);
+        assert_eq!(sent.next, None);
+        let heights: Vec<_> = sent.records.iter().map(|r| r.height).collect();
+        assert_eq!(heights, [1, 1, 2]);
+
+        let received = history.page(&bob, 0, 10);
+        assert_eq!(received.total, 2);
+        assert_eq!(received.records[0].hash, txs[0].signed_tx().hash());
+        assert_eq!(received.records[1].hash, later[0].signed_tx().hash());
+
+        assert_eq!(history.page(&Address::random(rng()), 0, 10).total, 0);
+    }
+
+    #[test]
+    fn paginate() {
+        let alice = Signer::random();
+        let bob = Address::random(rng());
+        let history = History::new();
+        for nonce in 0..5 {
+            history
+                .record(
+                    nonce,
+                    &block_tx(&alice, nonce, &bob, TxKind::Transfer),
+                    &APPLIED,
+                )
+                .unwrap();
+        }
+
+        let first = history.page(&bob, 0, 2);
+        assert_eq!(first.records.len(), 2);
+        assert_eq!(first.next, Some(2));
+        let last = history.page(&bob, 4, 2);
+        assert_eq!(last.records[0].height, 4);
+        assert_eq!(last.next, None);
+        assert!(history.page(&bob, 10, 2).records.is_empty());
+        assert_eq!(history.page(&bob, 0, usize::MAX).records.len(), 5);
+    }
+
+    #[test]
+    fn index_htlc_beneficiaries() {
+        let alice = Signer::random();
+        let bob = Address::random(rng());
+        let relayer = Signer::random();
+        let lock = TxKind::HtlcLock {
+            hashlock: keccak256(b"secret"),
+            timeout: TimeLock::Height(10),
+        };
+        let id = contract_address(&alice.address(), 0);
+        let history = History::new();
+        history
+            .record(1, &block_tx(&alice, 0, &bob, lock.clone()), &APPLIED)
+            .unwrap();
+        history
+            .record(2, &block_tx(&relayer, 0, &id, TxKind::HtlcClaim), &APPLIED)
+            .unwrap();
+        history
+            .record(
+                11,
+                &block_tx(&relayer, 1, &id, TxKind::HtlcRefund),
+                &APPLIED,
+            )
+            .unwrap();
+
+        // the claim pays bob, the refund pays alice
+        let heights = |address: &Address| {
+            let page = history.page(address, 0, 10);
+            page.records.iter().map(|r| r.height).collect::<Vec<_>>()
+        };
+        assert_eq!(heights(&bob), [1, 2]);
+        assert_eq!(heights(&alice.address()), [1, 11]);
+        assert_eq!(heights(&id), [2, 11]);
+
+        // a reverted lock creates no HTLC, a claim of its id pays nobody
+        let carol = Address::random(rng());
+        let reverted = Receipt {
+            gas_used: 21,
+            revert: Some("Invalid HTLC".to_string()),
+        };
+        let failed = contract_address(&alice.address(), 1);
+        history
+            .record(12, &block_tx(&alice, 1, &carol, lock), &reverted)
+            .unwrap();
+        history
+            .record(
+                13,
+                &block_tx(&relayer, 2, &failed, TxKind::HtlcClaim),
+                &APPLIED,
+            )
+            .unwrap();
+        assert_eq!(heights(&carol), [12]);
+    }
+
+    #[test]
+    fn reject_out_of_order_heights() {
+        let alice = Signer::random();
+        let bob = Address::random(rng());
+        let history = History::new();
+        history
+            .record(5, &block_tx(&alice, 0, &bob, TxKind::Transfer), &APPLIED)
+            .unwrap();
+        history
+            .record(5, &block_tx(&alice, 1, &bob, TxKind::Transfer), &APPLIED)
+            .unwrap();
+        assert!(
+            history
+                .record(4, &block_tx(&alice, 2, &bob, TxKind::Transfer), &APPLIED)
+                .is_err()
+        );
+        assert!(history.check(5).is_ok());
+        assert!(history.check(4).is_err());
+        assert_eq!(history.page(&bob, 0, 10).total, 2);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::database::timelock::{BlockInfo, TimeLock};
+use alloy_primitives::{B256, keccak256};
+use anyhow::Result;
+use bytelink_crypto::address::Address;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// A hash-time-locked contract, funds held in escrow until the recipient claims them with the
+/// preimage of the hashlock, or the sender is refunded once the timeout has passed.
+///
+/// ```text
+///          lock                  claim (preimage)
+/// sender ────────► escrow ──────────────────────────► recipient
+///                    │        until the timeout
+///                    │
+///                    │       refund
+///                    └──────────────────────────────► sender
+///                             after the timeout
+/// ```
+/// An HTLC is identified by an address, derived from the locking transaction like the
+/// address of a contract, see [`contract_address`][crate::vm::contract_address].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Htlc {
+    pub sender: Address,
+    pub recipient: Address,
+    pub amount: u64,
+    /// Keccak-256 hash of the preimage
+    pub hashlock: B256,
+    /// the last point at which the funds can be claimed
+    pub timeout: TimeLock,
+}
+
+impl Htlc {
+    /// Whether the timeout has passed as of the block, i.e. the funds can only be refunded.
+    pub fn is_expired(&self, block: &BlockInfo) -> bool {
+        self.timeout.compare(block) == Ordering::Greater
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(20 + 20 + 8 + 32 + 1 + 8);
+        buf.extend_from_slice(self.sender.as_bytes());
+        buf.extend_from_slice(self.recipient.as_bytes());
+        buf.extend_from_slice(&self.amount.to_be_bytes());
+        buf.extend_from_slice(self.hashlock.as_slice());
+        buf.push(self.timeout.tag());
+        buf.extend_from_slice(&self.timeout.value().to_be_bytes());
+        buf
+    }
+
+    /// Keccak-256 hash of the [`encode`][Htlc::encode]d HTLC, its leaf in the state trie.
+    pub fn hash(&self) -> B256 {
+        keccak256(self.encode())
+    }
+
+    /// Check that the preimage unlocks the hashlock.
+    pub fn unlock(&self, preimage: &[u8]) -> Result<()> {
+        if keccak256(preimage) != self.hashlock {
+            anyhow::bail!("Invalid preimage: hash does not match the hashlock");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::htlc::Htlc;
+    use crate::database::timelock::{BlockInfo, TimeLock};
+    use alloy_primitives::keccak256;
+    use bytelink_crypto::address::Address;
+    use rand::rng;
+
+    #[test]
+    fn unlock_until_timeout() {
+        let htlc = Htlc {
+            sender: Address::random(rng()),
+            recipient: Address::random(rng()),
+            amount: 100,
+            hashlock: keccak256(b"secret"),
+            timeout: TimeLock::Height(10),
+        };
+        assert!(htlc.unlock(b"secret").is_ok());
+        assert!(htlc.unlock(b"guess").is_err());
+
+        let at = |height| BlockInfo {
+            height,
+            timestamp: 0,
+        };
+        assert!(!htlc.is_expired(&at(10)));
+        assert!(htlc.is_expired(&at(11)));
+    }
+}
@@ -0,0 +1,197 @@
+use alloy_primitives::keccak256;
+use anyhow::Result;
+use bytelink_crypto::address::Address;
+use bytelink_crypto::signature::Signature;
+use serde::{Deserialize, Serialize};
+
+/// The maximum number of owners of a multisig account.
+pub const MAX_OWNERS: usize = 16;
+
+/// An M-of-N multisig account, owned by N addresses of which M must sign every transaction.
+///
+/// The account holds no key, its address is derived from the owners and the threshold, so
+/// that the configuration does not need to be registered on chain: it is carried by every
+/// transaction of the account and checked against the sender address.
+///
+/// A multisig is therefore not a kind of account stored in the
+/// [`Database`][crate::database::Database] or committed to the state root. Its balance and
+/// nonce are those of the plain [`Account`][crate::database::account::Account] at its address,
+/// which can be funded before any transaction reveals the owners.
+/// ```text
+/// ┌───────────┬─────┬──────────┬─────┬──────────┐
+/// │ threshold │  n  │ owner #1 │ ... │ owner #n │
+/// │    (1)    │ (1) │   (20)   │     │   (20)   │
+/// └───────────┴─────┴──────────┴─────┴──────────┘
+/// ```
+/// The owners are sorted, so the same set of owners always gives the same address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "MultisigRepr")]
+pub struct Multisig {
+    threshold: u8,
+    owners: Vec<Address>,
+}
+
+#[derive(Deserialize)]
+struct MultisigRepr {
+    threshold: u8,
+    owners: Vec<Address>,
+}
+
+impl TryFrom<MultisigRepr> for Multisig {
+    type Error = anyhow::Error;
+
+    fn try_from(repr: MultisigRepr) -> Result<Self> {
+        Self::new(repr.threshold, repr.owners)
+    }
+}
+
+impl Multisig {
+    /// Create a multisig account requiring `threshold` signatures out of the distinct `owners`.
+    pub fn new(threshold: u8, mut owners: Vec<Address>) -> Result<Self> {
+        if owners.is_empty() || owners.len() > MAX_OWNERS {
+            anyhow::bail!(
+                "Invalid multisig: expected 1 to {MAX_OWNERS} owners, got {}",
+                owners.len()
+            );
+        }
+        if threshold == 0 || threshold as usize > owners.len() {
+            anyhow::bail!(
+                "Invalid multisig: threshold must be between 1 and {}, got {threshold}",
+                owners.len()
+            );
+        }
+
+        owners.sort_unstable_by_key(|owner| *owner.as_bytes());
+        if let Some(pair) = owners.windows(2).find(|pair| pair[0] == pair[1]) {
+            anyhow::bail!("Invalid multisig: duplicate owner {}", pair[0]);
+        }
+
+        Ok(Multisig { threshold, owners })
+    }
+
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// The owners, sorted by address.
+    pub fn owners(&self) -> &[Address] {
+        &self.owners
+    }
+
+    /// The address of the account, the last 20 bytes of the Keccak-256 hash of its encoding.
+    pub fn address(&self) -> Address {
+        Address::from_slice(&keccak256(self.encode())[12..]).expect("hash is 32 bytes long")
+    }
+
+    /// Encode the account into its canonical binary form.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(2 + 20 * self.owners.len());
+        self.encode_into(&mut buf);
+        buf
+    }
+
+    pub(crate) fn encode_into(&self, buf: &mut Vec<u8>) {
+        buf.push(self.threshold);
+        buf.push(self.owners.len() as u8);
+        for owner in &self.owners {
+            buf.extend_from_slice(owner.as_bytes());
+        }
+    }
+
+    /// Check that at least `threshold` distinct owners signed the message.
+    pub fn verify(&self, msg: &[u8], signatures: &[Signature]) -> Result<()> {
+        let mut signers = Vec::with_capacity(signatures.len());
+        for signature in signatures {
+            if !signature.is_low_s() {
+                anyhow::bail!("Invalid multisig signature: non-canonical high-s value");
+            }
+            let signer = signature.recover_address_from_msg(msg)?;
+            if !self.owners.contains(&signer) {
+                anyhow::bail!("Invalid multisig signature: {signer} is not an owner");
+            }
+            if signers.contains(&signer) {
+                anyhow::bail!("Invalid multisig signature: {signer} signed more than once");
+            }
+            signers.push(signer);
+        }
+
+        if signers.len() < self.threshold as usize {
+            anyhow::bail!(
+                "Invalid multisig signature: {} of {} required signatures",
+                signers.len(),
+                self.threshold
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::multisig::{MAX_OWNERS, Multisig};
+    use bytelink_crypto::address::Address;
+    use bytelink_crypto::signer::{Signer, SignerSync};
+
+    fn owners(n: usize) -> Vec<Signer> {
+        (0..n).map(|_| Signer::random()).collect()
+    }
+
+    fn addresses(signers: &[Signer]) -> Vec<Address> {
+        signers.iter().map(|s| s.address()).collect()
+    }
+
+    #[test]
+    fn address_ignores_owner_order() {
+        let signers = owners(3);
+        let mut reversed = addresses(&signers);
+        reversed.reverse();
+
+        let multisig = Multisig::new(2, addresses(&signers)).unwrap();
+        assert_eq!(
+            multisig.address(),
+            Multisig::new(2, reversed).unwrap().address()
+        );
+        assert_ne!(
+            multisig.address(),
+            Multisig::new(3, addresses(&signers)).unwrap().address()
+        );
+        assert_eq!(multisig.encode().len(), 2 + 3 * 20);
+    }
+
+    #[test]
+    fn reject_invalid_config() {
+        let signers = addresses(&owners(3));
+        assert!(Multisig::new(0, signers.clone()).is_err());
+        assert!(Multisig::new(4, signers.clone()).is_err());
+        assert!(Multisig::new(1, vec![]).is_err());
+        assert!(Multisig::new(1, vec![signers[0].clone(), signers[0].clone()]).is_err());
+
+        let many = addresses(&owners(MAX_OWNERS + 1));
+        assert!(Multisig::new(1, many).is_err());
+
+        // the JSON form is validated as well
+        let json = serde_json::json!({ "threshold": 4, "owners": signers });
+        assert!(serde_json::from_value::<Multisig>(json).is_err());
+    }
+
+    #[test]
+    fn verify_threshold() {
+        let signers = owners(3);
+        let multisig = Multisig::new(2, addresses(&signers)).unwrap();
+        let sign = |signer: &Signer| signer.eip191_sign_msg(b"transfer").unwrap();
+
+        let two = [sign(&signers[0]), sign(&signers[2])];
+        assert!(multisig.verify(b"transfer", &two).is_ok());
+        assert!(multisig.verify(b"other", &two).is_err());
+
+        // below threshold
+        assert!(multisig.verify(b"transfer", &two[..1]).is_err());
+        // the same owner twice
+        let twice = [sign(&signers[0]), sign(&signers[0])];
+        assert!(multisig.verify(b"transfer", &twice).is_err());
+        // not an owner
+        let stranger = [sign(&signers[0]), sign(&Signer::random())];
+        assert!(multisig.verify(b"transfer", &stranger).is_err());
+    }
+}
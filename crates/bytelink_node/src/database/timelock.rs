@@ -0,0 +1,119 @@
+use anyhow::Result;
+use jiff::Timestamp;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// The block a transaction is included in, against which its time locks are checked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockInfo {
+    pub height: u64,
+    /// unix timestamp in milliseconds
+    pub timestamp: u64,
+}
+
+/// A point of the chain history bounding the validity of a transaction, in JSON:
+/// ```json
+/// { "height": 120000 }
+/// { "timestamp": 1767225600000 }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeLock {
+    /// a block height
+    Height(u64),
+    /// a unix timestamp in milliseconds, compared to the block timestamp
+    Timestamp(u64),
+}
+
+impl TimeLock {
+    /// Whether the block is before, at or after this point.
+    pub fn compare(&self, block: &BlockInfo) -> Ordering {
+        match self {
+            TimeLock::Height(height) => block.height.cmp(height),
+            TimeLock::Timestamp(timestamp) => block.timestamp.cmp(timestamp),
+        }
+    }
+
+    pub(crate) fn tag(&self) -> u8 {
+        match self {
+            TimeLock::Height(_) => 1,
+            TimeLock::Timestamp(_) => 2,
+        }
+    }
+
+    pub(crate) fn value(&self) -> u64 {
+        match self {
+            TimeLock::Height(value) | TimeLock::Timestamp(value) => *value,
+        }
+    }
+
+    pub(crate) fn from_tag(tag: u8, value: u64) -> Result<Self> {
+        match tag {
+            1 => Ok(TimeLock::Height(value)),
+            2 => Ok(TimeLock::Timestamp(value)),
+            tag => anyhow::bail!("Unknown time lock kind: {tag:#04x}"),
+        }
+    }
+}
+
+/// Parse a block height, i.e. `120000`, or an RFC 3339 timestamp, i.e. `2026-01-01T00:00:00Z`.
+impl FromStr for TimeLock {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if let Ok(height) = s.parse() {
+            return Ok(TimeLock::Height(height));
+        }
+        let timestamp: Timestamp = s.parse().map_err(|_| {
+            anyhow::anyhow!("Invalid time lock: {s} is neither a block height nor a timestamp")
+        })?;
+        let millis = u64::try_from(timestamp.as_millisecond())
+            .map_err(|_| anyhow::anyhow!("Invalid time lock: {s} is before the unix epoch"))?;
+        Ok(TimeLock::Timestamp(millis))
+    }
+}
+
+/// Where a block stands in the validity window of a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Validity {
+    /// the transaction cannot be included yet
+    Pending,
+    Valid,
+    /// the transaction can no longer be included
+    Expired,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::timelock::{BlockInfo, TimeLock};
+    use std::cmp::Ordering;
+
+    #[test]
+    fn compare_to_block() {
+        let block = BlockInfo {
+            height: 10,
+            timestamp: 1_000,
+        };
+        assert_eq!(TimeLock::Height(9).compare(&block), Ordering::Greater);
+        assert_eq!(TimeLock::Height(10).compare(&block), Ordering::Equal);
+        assert_eq!(TimeLock::Timestamp(1_001).compare(&block), Ordering::Less);
+    }
+
+    #[test]
+    fn parse_time_lock() {
+        assert_eq!(
+            "120000".parse::<TimeLock>().unwrap(),
+            TimeLock::Height(120000)
+        );
+        assert_eq!(
+            "2026-01-01T00:00:00Z".parse::<TimeLock>().unwrap(),
+            TimeLock::Timestamp(1_767_225_600_000)
+        );
+        assert!("soon".parse::<TimeLock>().is_err());
+        assert!("1969-12-31T00:00:00Z".parse::<TimeLock>().is_err());
+
+        let json = serde_json::to_string(&TimeLock::Height(7)).unwrap();
+        assert_eq!(json, r#"{"height":7}"#);
+    }
+}
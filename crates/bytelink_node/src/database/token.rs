@@ -0,0 +1,41 @@
+use alloy_primitives::{B256, keccak256};
+use bytelink_crypto::address::Address;
+use serde::{Deserialize, Serialize};
+
+/// A native fungible token, created with a fixed supply credited to its issuer.
+///
+/// A token is identified by an address, derived from the creating transaction like the
+/// address of a contract, see [`contract_address`][crate::vm::contract_address]. Token
+/// balances are kept apart from the base coin, keyed by `(token id, holder)`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Token {
+    issuer: Address,
+    supply: u64,
+}
+
+impl Token {
+    pub fn new(issuer: Address, supply: u64) -> Self {
+        Token { issuer, supply }
+    }
+
+    pub fn issuer(&self) -> &Address {
+        &self.issuer
+    }
+
+    /// The total supply, which never changes.
+    pub fn supply(&self) -> u64 {
+        self.supply
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(20 + 8);
+        buf.extend_from_slice(self.issuer.as_bytes());
+        buf.extend_from_slice(&self.supply.to_be_bytes());
+        buf
+    }
+
+    /// Keccak-256 hash of the [`encode`][Token::encode]d token, its leaf in the state trie.
+    pub fn hash(&self) -> B256 {
+        keccak256(self.encode())
+    }
+}
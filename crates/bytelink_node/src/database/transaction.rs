@@ -1,62 +1,887 @@
+use crate::database::multisig::Multisig;
+use crate::database::timelock::{BlockInfo, TimeLock, Validity};
+use alloy_primitives::{B256, keccak256};
 use anyhow::Result;
 use bytelink_crypto::address::Address;
+use bytelink_crypto::batch::{self, BatchError};
+use bytelink_crypto::merkle::{MerkleTree, merkle_root};
 use bytelink_crypto::signature::Signature;
-use bytelink_crypto::signer::SignerSync;
+use bytelink_crypto::signer::{AsyncSigner, SignerSync};
 use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Version tag leading the binary encoding of a [`SignedTx`] in the legacy format.
+const SIGNED_TX_V0: u8 = 0x00;
+/// Version tag leading the binary encoding of a [`SignedTx`].
+const SIGNED_TX_V1: u8 = 0x01;
+/// Version tag leading the binary encoding of a [`SignedTx`] sent by a multisig account.
+const SIGNED_TX_MULTISIG: u8 = 0x02;
+/// Flag of the version tag marking a time-locked transaction, see [`Transaction::encode_into`].
+const TIME_LOCKED: u8 = 0x80;
+/// Flag of the version tag marking a transaction of another kind than a transfer.
+const TYPED: u8 = 0x40;
+/// Flag of the version tag marking a transaction bound to a chain id.
+const CHAIN_BOUND: u8 = 0x20;
+
+/// What a [`Transaction`] does besides sending its value, a transfer if omitted in JSON.
+///
+/// The token kinds move the native tokens of the [`token`][crate::database::token] module,
+/// `"kind": { "token_transfer": { "token": "0x..", "amount": 10 } }` in JSON.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TxKind {
+    /// send the value, running the code of the recipient with the data as input if it is a contract
+    #[default]
+    Transfer,
+    /// create a contract whose code is the data, the recipient must be the
+    /// [`contract_address`][crate::vm::contract_address] of the sender and nonce
+    Create,
+    /// create a token whose whole supply is credited to the sender, the recipient is the token
+    /// id and must be the [`contract_address`][crate::vm::contract_address] of the sender and nonce
+    CreateToken { supply: u64 },
+    /// send `amount` of the token to the recipient
+    TokenTransfer { token: Address, amount: u64 },
+    /// allow the recipient to spend up to `amount` of the token of the sender,
+    /// replacing any previous allowance
+    TokenApprove { token: Address, amount: u64 },
+    /// send `amount` of the token of `from` to the recipient, out of the allowance of the sender
+    TokenTransferFrom {
+        token: Address,
+        from: Address,
+        amount: u64,
+    },
+    /// hold the value in an [`Htlc`][crate::database::htlc::Htlc] for the recipient, whose id is
+    /// the [`contract_address`][crate::vm::contract_address] of the sender and nonce
+    HtlcLock { hashlock: B256, timeout: TimeLock },
+    /// pay out the HTLC whose id is the recipient, the data being the preimage of its hashlock
+    HtlcClaim,
+    /// refund the sender of the expired HTLC whose id is the recipient
+    HtlcRefund,
+}
+
+impl TxKind {
+    pub fn is_transfer(&self) -> bool {
+        *self == TxKind::Transfer
+    }
+
+    /// The tag of the kind, followed by its fields:
+    /// ```text
+    /// ┌─────┬────────────────────────────────────────┐
+    /// │ tag │ fields                                 │
+    /// ├─────┼────────────────────────────────────────┤
+    /// │  1  │ -                                      │
+    /// │  2  │ supply (8)                             │
+    /// │  3  │ token (20), amount (8)                 │
+    /// │  4  │ token (20), amount (8)                 │
+    /// │  5  │ token (20), from (20), amount (8)      │
+    /// │  6  │ hashlock (32), timeout (1 + 8)         │
+    /// │  7  │ -                                      │
+    /// │  8  │ -                                      │
+    /// └─────┴────────────────────────────────────────┘
+    /// ```
+    fn encode_into(&self, buf: &mut Vec<u8>) {
+        match self {
+            TxKind::Transfer => buf.push(0),
+            TxKind::Create => buf.push(1),
+            TxKind::CreateToken { supply } => {
+                buf.push(2);
+                buf.extend_from_slice(&supply.to_be_bytes());
+            }
+            TxKind::TokenTransfer { token, amount } => {
+                buf.push(3);
+                buf.extend_from_slice(token.as_bytes());
+                buf.extend_from_slice(&amount.to_be_bytes());
+            }
+            TxKind::TokenApprove { token, amount } => {
+                buf.push(4);
+                buf.extend_from_slice(token.as_bytes());
+                buf.extend_from_slice(&amount.to_be_bytes());
+            }
+            TxKind::TokenTransferFrom {
+                token,
+                from,
+                amount,
+            } => {
+                buf.push(5);
+                buf.extend_from_slice(token.as_bytes());
+                buf.extend_from_slice(from.as_bytes());
+                buf.extend_from_slice(&amount.to_be_bytes());
+            }
+            TxKind::HtlcLock { hashlock, timeout } => {
+                buf.push(6);
+                buf.extend_from_slice(hashlock.as_slice());
+                buf.push(timeout.tag());
+                buf.extend_from_slice(&timeout.value().to_be_bytes());
+            }
+            TxKind::HtlcClaim => buf.push(7),
+            TxKind::HtlcRefund => buf.push(8),
+        }
+    }
+
+    fn decode_from(reader: &mut Reader<'_>) -> Result<Self> {
+        match reader.u8()? {
+            // a transfer is never tagged
+            1 => Ok(TxKind::Create),
+            2 => Ok(TxKind::CreateToken {
+                supply: reader.u64()?,
+            }),
+            3 => Ok(TxKind::TokenTransfer {
+                token: reader.address()?,
+                amount: reader.u64()?,
+            }),
+            4 => Ok(TxKind::TokenApprove {
+                token: reader.address()?,
+                amount: reader.u64()?,
+            }),
+            5 => Ok(TxKind::TokenTransferFrom {
+                token: reader.address()?,
+                from: reader.address()?,
+                amount: reader.u64()?,
+            }),
+            6 => Ok(TxKind::HtlcLock {
+                hashlock: B256::from(reader.array::<32>()?),
+                timeout: TimeLock::from_tag(reader.u8()?, reader.u64()?)?,
+            }),
+            7 => Ok(TxKind::HtlcClaim),
+            8 => Ok(TxKind::HtlcRefund),
+            tag => anyhow::bail!("Unknown transaction kind: {tag:#04x}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Transaction {
     /// nonce of sender
     pub nonce: u64,
-    /// sender address
-    pub from: Address, // TODO: consider removing 'from' field to save space, as it can be derived from the signature
     /// recipient address
     pub to: Address,
     /// total amount of transaction to be sent
     pub value: u64,
     /// rewards for processing the transaction
     pub tip: u64,
-    /// data payload
+    /// data payload, hex encoded in JSON
+    #[serde(with = "alloy_primitives::hex")]
     pub data: Vec<u8>,
+    #[serde(default, skip_serializing_if = "TxKind::is_transfer")]
+    pub kind: TxKind,
+    /// the transaction cannot be included in a block before this point
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_after: Option<TimeLock>,
+    /// the transaction cannot be included in a block after this point
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub valid_until: Option<TimeLock>,
+    /// the [`chain_id`][crate::genesis::Genesis::chain_id] of the network the transaction is
+    /// meant for, so that it cannot be replayed on another one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u16>,
 }
 
 impl Transaction {
+    /// A plain transfer of `value` to `to`, without data, time locks or chain id.
+    pub fn new(nonce: u64, to: Address, value: u64, tip: u64) -> Self {
+        Transaction {
+            nonce,
+            to,
+            value,
+            tip,
+            data: vec![],
+            kind: TxKind::Transfer,
+            valid_after: None,
+            valid_until: None,
+            chain_id: None,
+        }
+    }
+
     pub fn sign_by<S: SignerSync>(self, signer: &S) -> Result<SignedTx> {
-        let encoded = serde_json::to_vec(&self)?;
-        let signature = signer.eip191_sign_msg(&encoded)?;
-        Ok(SignedTx {
-            transaction: self,
-            signature,
+        let signature = signer.eip191_sign_msg(&self.signing_message()?)?;
+        SignedTx::new(self, signature)
+    }
+
+    /// [`sign_by`][Transaction::sign_by] with a signer that does not hold the key in-process,
+    /// such as the remote signer of `bytelink_crypto`.
+    pub async fn sign_by_async<S: AsyncSigner>(self, signer: &S) -> Result<SignedTx> {
+        let signature = signer
+            .eip191_sign_msg_async(&self.signing_message()?)
+            .await?;
+        SignedTx::new(self, signature)
+    }
+
+    /// Approve the transaction as one of the owners of a multisig account. The result only
+    /// carries the signature of `signer`, it has to be [merged][SignedTx::merge] with the
+    /// ones of the other owners until the threshold is reached.
+    pub fn sign_multisig<S: SignerSync>(self, multisig: Multisig, signer: &S) -> Result<SignedTx> {
+        let signature = signer.eip191_sign_msg(&self.multisig_message(&multisig)?)?;
+        self.approve(multisig, signature)
+    }
+
+    /// [`sign_multisig`][Transaction::sign_multisig] with a signer that does not hold the key in-process.
+    pub async fn sign_multisig_async<S: AsyncSigner>(
+        self,
+        multisig: Multisig,
+        signer: &S,
+    ) -> Result<SignedTx> {
+        let signature = signer
+            .eip191_sign_msg_async(&self.multisig_message(&multisig)?)
+            .await?;
+        self.approve(multisig, signature)
+    }
+
+    /// A multisig transaction carrying the signature of a single owner.
+    fn approve(self, multisig: Multisig, signature: Signature) -> Result<SignedTx> {
+        let owner = signature.recover_address_from_msg(&self.multisig_message(&multisig)?)?;
+        if !multisig.owners().contains(&owner) {
+            anyhow::bail!("Invalid multisig signer: {owner} is not an owner");
+        }
+        SignedTx::new_multisig(self, multisig, vec![signature])
+    }
+
+    /// The message covered by the signature, the sender is not part of it.
+    fn signing_message(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(&SigningMessage::new(self))?)
+    }
+
+    /// The message covered by the signatures of the owners of a multisig account.
+    fn multisig_message(&self, multisig: &Multisig) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(&MultisigMessage {
+            multisig: multisig.address(),
+            transaction: SigningMessage::new(self),
+        })?)
+    }
+
+    /// Whether the transaction can be included in the block, as far as its time locks tell.
+    /// Both bounds are inclusive.
+    pub fn validity(&self, block: &BlockInfo) -> Validity {
+        if self
+            .valid_after
+            .is_some_and(|lock| lock.compare(block) == Ordering::Less)
+        {
+            Validity::Pending
+        } else if self
+            .valid_until
+            .is_some_and(|lock| lock.compare(block) == Ordering::Greater)
+        {
+            Validity::Expired
+        } else {
+            Validity::Valid
+        }
+    }
+
+    pub fn is_time_locked(&self) -> bool {
+        self.valid_after.is_some() || self.valid_until.is_some()
+    }
+
+    /// The optional parts follow the data, in order:
+    /// - the [`TxKind`] tag and its fields, unless it is a transfer
+    /// - the time locks of a time-locked transaction, each one as a kind tag, `0` if absent,
+    ///   `1` for a height and `2` for a timestamp, and the 8-byte value if present
+    /// - the 2-byte chain id of a chain-bound transaction
+    fn encode_into(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.nonce.to_be_bytes());
+        buf.extend_from_slice(self.to.as_bytes());
+        buf.extend_from_slice(&self.value.to_be_bytes());
+        buf.extend_from_slice(&self.tip.to_be_bytes());
+        buf.extend_from_slice(&(self.data.len() as u32).to_be_bytes());
+        buf.extend_from_slice(&self.data);
+        if !self.kind.is_transfer() {
+            self.kind.encode_into(buf);
+        }
+        if self.is_time_locked() {
+            for lock in [&self.valid_after, &self.valid_until] {
+                match lock {
+                    Some(lock) => {
+                        buf.push(lock.tag());
+                        buf.extend_from_slice(&lock.value().to_be_bytes());
+                    }
+                    None => buf.push(0),
+                }
+            }
+        }
+        if let Some(chain_id) = self.chain_id {
+            buf.extend_from_slice(&chain_id.to_be_bytes());
+        }
+    }
+
+    /// Decode a transaction whose optional parts are marked by the version tag `flags`.
+    fn decode_from(reader: &mut Reader<'_>, flags: u8) -> Result<Self> {
+        let mut transaction = Transaction {
+            nonce: reader.u64()?,
+            to: reader.address()?,
+            value: reader.u64()?,
+            tip: reader.u64()?,
+            data: reader.bytes()?,
+            kind: TxKind::Transfer,
+            valid_after: None,
+            valid_until: None,
+            chain_id: None,
+        };
+        if flags & TYPED != 0 {
+            transaction.kind = TxKind::decode_from(reader)?;
+        }
+        if flags & TIME_LOCKED != 0 {
+            transaction.valid_after = reader.time_lock()?;
+            transaction.valid_until = reader.time_lock()?;
+            if !transaction.is_time_locked() {
+                anyhow::bail!("Invalid transaction encoding: time-locked without time lock");
+            }
+        }
+        if flags & CHAIN_BOUND != 0 {
+            transaction.chain_id = Some(u16::from_be_bytes(reader.array()?));
+        }
+        Ok(transaction)
+    }
+
+    /// The version tag flags of the transaction.
+    fn flags(&self) -> u8 {
+        let mut flags = 0;
+        if !self.kind.is_transfer() {
+            flags |= TYPED;
+        }
+        if self.is_time_locked() {
+            flags |= TIME_LOCKED;
+        }
+        if self.chain_id.is_some() {
+            flags |= CHAIN_BOUND;
+        }
+        flags
+    }
+}
+
+/// The JSON message covered by the signature of a [`Transaction`].
+///
+/// It follows the JSON form of the transaction, except for the data payload, which is signed
+/// as an array of bytes like in the first transaction format whatever its encoding in the API.
+#[derive(Serialize)]
+struct SigningMessage<'a> {
+    nonce: u64,
+    to: &'a Address,
+    value: u64,
+    tip: u64,
+    data: &'a [u8],
+    #[serde(skip_serializing_if = "TxKind::is_transfer")]
+    kind: TxKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    valid_after: Option<TimeLock>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    valid_until: Option<TimeLock>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chain_id: Option<u16>,
+}
+
+impl<'a> SigningMessage<'a> {
+    fn new(tx: &'a Transaction) -> Self {
+        SigningMessage {
+            nonce: tx.nonce,
+            to: &tx.to,
+            value: tx.value,
+            tip: tx.tip,
+            data: &tx.data,
+            kind: tx.kind.clone(),
+            valid_after: tx.valid_after,
+            valid_until: tx.valid_until,
+            chain_id: tx.chain_id,
+        }
+    }
+}
+
+/// The legacy transaction format, which carries the sender address next to the signature.
+/// Only kept to decode and verify transactions signed in this format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LegacyTransaction {
+    nonce: u64,
+    from: Address,
+    to: Address,
+    value: u64,
+    tip: u64,
+    #[serde(with = "alloy_primitives::hex")]
+    data: Vec<u8>,
+}
+
+impl LegacyTransaction {
+    fn new(tx: &Transaction, from: Address) -> Self {
+        LegacyTransaction {
+            nonce: tx.nonce,
+            from,
+            to: tx.to.clone(),
+            value: tx.value,
+            tip: tx.tip,
+            data: tx.data.clone(),
+        }
+    }
+
+    /// The message signed by the first transaction format, the JSON of its fields in order
+    /// with the data payload as an array of bytes.
+    fn signing_message(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(&LegacyMessage {
+            nonce: self.nonce,
+            from: &self.from,
+            to: &self.to,
+            value: self.value,
+            tip: self.tip,
+            data: &self.data,
+        })?)
+    }
+
+    fn into_transaction(self) -> Transaction {
+        Transaction {
+            data: self.data,
+            ..Transaction::new(self.nonce, self.to, self.value, self.tip)
+        }
+    }
+
+    fn encode_into(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.nonce.to_be_bytes());
+        buf.extend_from_slice(self.from.as_bytes());
+        buf.extend_from_slice(self.to.as_bytes());
+        buf.extend_from_slice(&self.value.to_be_bytes());
+        buf.extend_from_slice(&self.tip.to_be_bytes());
+        buf.extend_from_slice(&(self.data.len() as u32).to_be_bytes());
+        buf.extend_from_slice(&self.data);
+    }
+
+    fn decode_from(reader: &mut Reader<'_>) -> Result<Self> {
+        Ok(LegacyTransaction {
+            nonce: reader.u64()?,
+            from: reader.address()?,
+            to: reader.address()?,
+            value: reader.u64()?,
+            tip: reader.u64()?,
+            data: reader.bytes()?,
         })
     }
 }
 
+/// The message covered by the signature of a [`LegacyTransaction`].
+#[derive(Serialize)]
+struct LegacyMessage<'a> {
+    nonce: u64,
+    from: &'a Address,
+    to: &'a Address,
+    value: u64,
+    tip: u64,
+    data: &'a [u8],
+}
+
+/// What authorizes a [`SignedTx`], which decides the message covered by the signatures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Witness {
+    /// a signature of the sender over the legacy message, see [`LegacyTransaction`]
+    Legacy(Signature),
+    /// a signature of the sender, which is recovered from it
+    Single(Signature),
+    /// signatures of the owners of the multisig sender, see [`MultisigMessage`]
+    Multisig {
+        multisig: Multisig,
+        /// sorted by their binary form, so that they cannot be reordered
+        signatures: Vec<Signature>,
+    },
+}
+
+/// The message signed by the owners of a [`Multisig`] account. It is bound to the account, so
+/// the approval of an owner cannot be replayed on their own account or on another multisig account.
+#[derive(Serialize)]
+struct MultisigMessage<'a> {
+    multisig: Address,
+    transaction: SigningMessage<'a>,
+}
+
+/// A transaction signed by its sender.
+///
+/// The sender is not part of the transaction, it is recovered from the signature when the
+/// signed transaction is created or decoded, and cached.
+///
+/// In JSON, a signed transaction is the signed [`Transaction`] next to its hex encoded signature:
+/// ```json
+/// {
+///   "transaction": { "nonce": 0, "to": "0x..", "value": 100, "tip": 1, "data": "0x" },
+///   "signature": "0x.."
+/// }
+/// ```
+/// The canonical binary form used on disk and over the network is versioned, integers are big-endian:
+/// ```text
+/// ┌──────┬───────┬──────┬───────┬─────┬───────────┬───────┬───────────┐
+/// │ 0x01 │ nonce │  to  │ value │ tip │ len(data) │ data  │ signature │
+/// │ (1)  │  (8)  │ (20) │  (8)  │ (8) │    (4)    │ (len) │   (65)    │
+/// └──────┴───────┴──────┴───────┴─────┴───────────┴───────┴───────────┘
+/// ```
+/// The legacy format, tagged `0x00`, additionally carries the 20-byte sender address right
+/// after the nonce, and its JSON form has a `"from"` field. It can still be decoded, in which
+/// case the signature covers the legacy message and the declared sender must match the recovered one.
+///
+/// A transaction sent by a [`Multisig`] account carries the account configuration and the
+/// signatures of its owners instead, its sender is the address of the account. In JSON, it
+/// has `"multisig"` and `"signatures"` fields, and its binary form is tagged `0x02`:
+/// ```text
+/// ┌──────┬─────────────┬──────────┬───────┬──────────────┐
+/// │ 0x02 │ transaction │ multisig │ count │  signatures  │
+/// │ (1)  │             │          │  (1)  │ (65 * count) │
+/// └──────┴─────────────┴──────────┴───────┴──────────────┘
+/// ```
+///
+/// A time-locked transaction sets the `0x80` bit of the version tag, a transaction of another
+/// [`TxKind`] than a transfer sets the `0x40` bit and a chain-bound transaction sets the `0x20`
+/// bit, their fields follow the data payload.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "SignedTxRepr", into = "SignedTxRepr")]
 pub struct SignedTx {
     pub transaction: Transaction,
-    signature: Signature,
+    witness: Witness,
+    /// sender recovered from the signature, or the multisig address
+    sender: Address,
 }
 
 impl SignedTx {
-    pub fn verify(&self) -> Result<()> {
-        let encoded = serde_json::to_vec(&self.transaction)?;
-        let recovered_address = self.signature.recover_address_from_msg(&encoded)?;
+    /// Create a signed transaction, recovering its sender from the signature.
+    pub fn new(transaction: Transaction, signature: Signature) -> Result<Self> {
+        let sender = signature.recover_address_from_msg(&transaction.signing_message()?)?;
+        Ok(SignedTx {
+            transaction,
+            witness: Witness::Single(signature),
+            sender,
+        })
+    }
 
-        if recovered_address != self.transaction.from {
+    /// Create a transaction sent by a multisig account, signed by some of its owners.
+    /// The signatures are only checked by [`verify`][SignedTx::verify], so that a
+    /// transaction below the threshold can be passed around to collect more of them.
+    pub fn new_multisig(
+        transaction: Transaction,
+        multisig: Multisig,
+        mut signatures: Vec<Signature>,
+    ) -> Result<Self> {
+        signatures.sort_unstable_by_key(|signature| signature.as_bytes());
+        signatures.dedup();
+        if signatures.len() > multisig.owners().len() {
+            anyhow::bail!(
+                "Invalid multisig transaction: {} signatures for {} owners",
+                signatures.len(),
+                multisig.owners().len()
+            );
+        }
+        Ok(SignedTx {
+            transaction,
+            sender: multisig.address(),
+            witness: Witness::Multisig {
+                multisig,
+                signatures,
+            },
+        })
+    }
+
+    fn from_legacy(legacy: LegacyTransaction, signature: Signature) -> Result<Self> {
+        let sender = signature.recover_address_from_msg(&legacy.signing_message()?)?;
+        if sender != legacy.from {
             anyhow::bail!(
                 "Invalid transaction signature: recovered address does not match sender address"
             );
         }
+        Ok(SignedTx {
+            transaction: legacy.into_transaction(),
+            witness: Witness::Legacy(signature),
+            sender,
+        })
+    }
+
+    fn legacy(&self) -> LegacyTransaction {
+        LegacyTransaction::new(&self.transaction, self.sender.clone())
+    }
+
+    fn signing_message(&self) -> Result<Vec<u8>> {
+        match &self.witness {
+            Witness::Legacy(_) => self.legacy().signing_message(),
+            Witness::Single(_) => self.transaction.signing_message(),
+            Witness::Multisig { multisig, .. } => self.transaction.multisig_message(multisig),
+        }
+    }
+
+    /// Combine the owner signatures of two copies of the same multisig transaction.
+    pub fn merge(self, other: &SignedTx) -> Result<Self> {
+        match (self.witness, &other.witness) {
+            (
+                Witness::Multisig {
+                    multisig,
+                    mut signatures,
+                },
+                Witness::Multisig {
+                    multisig: other_multisig,
+                    signatures: other_signatures,
+                },
+            ) if multisig == *other_multisig && self.transaction == other.transaction => {
+                signatures.extend_from_slice(other_signatures);
+                Self::new_multisig(self.transaction, multisig, signatures)
+            }
+            _ => anyhow::bail!("Cannot merge signatures: not the same multisig transaction"),
+        }
+    }
+
+    pub fn verify(&self) -> Result<()> {
+        self.verify_fields()?;
+
+        // the transaction may have been modified after the sender was recovered
+        let msg = self.signing_message()?;
+        match &self.witness {
+            Witness::Legacy(signature) | Witness::Single(signature) => {
+                if signature.recover_address_from_msg(&msg)? != self.sender {
+                    anyhow::bail!(
+                        "Invalid transaction signature: recovered address does not match sender address"
+                    );
+                }
+            }
+            Witness::Multisig {
+                multisig,
+                signatures,
+            } => multisig.verify(&msg, signatures)?,
+        }
+
+        Ok(())
+    }
+
+    /// The checks of [`verify`][SignedTx::verify] that do not need to recover the signature.
+    fn verify_fields(&self) -> Result<()> {
+        if !self.signatures().iter().all(Signature::is_low_s) {
+            anyhow::bail!("Invalid transaction signature: non-canonical high-s value");
+        }
 
-        if recovered_address == self.transaction.to {
+        if let (Some(after), Some(until)) =
+            (self.transaction.valid_after, self.transaction.valid_until)
+            && after.tag() == until.tag()
+            && after.value() > until.value()
+        {
+            anyhow::bail!("Invalid transaction: empty validity window");
+        }
+
+        // the legacy message does not cover the kind, time locks and chain id
+        if matches!(self.witness, Witness::Legacy(_)) && self.transaction.flags() != 0 {
+            anyhow::bail!("Invalid transaction: legacy transactions are plain transfers");
+        }
+
+        if self.sender == self.transaction.to {
             anyhow::bail!("Invalid transaction: sender and recipient addresses cannot be the same");
         }
 
         Ok(())
     }
+
+    /// [`verify`][SignedTx::verify] many transactions at once, such as the ones of a block,
+    /// recovering the signatures in parallel. Reports the position of every invalid transaction.
+    pub fn verify_batch<'a, I>(txs: I) -> std::result::Result<(), BatchError>
+    where
+        I: IntoIterator<Item = &'a SignedTx>,
+    {
+        let mut failed = Vec::new();
+        let mut items = Vec::new();
+        let mut positions = Vec::new();
+        for (index, tx) in txs.into_iter().enumerate() {
+            match tx.verify_fields().and_then(|_| tx.signing_message()) {
+                Ok(msg) => match &tx.witness {
+                    Witness::Legacy(signature) | Witness::Single(signature) => {
+                        items.push((*signature, msg, tx.sender.clone()));
+                        positions.push(index);
+                    }
+                    // the signers are not known in advance, the owners are checked in place
+                    Witness::Multisig {
+                        multisig,
+                        signatures,
+                    } => {
+                        if multisig.verify(&msg, signatures).is_err() {
+                            failed.push(index);
+                        }
+                    }
+                },
+                Err(_) => failed.push(index),
+            }
+        }
+
+        if let Err(err) = batch::verify_batch(&items) {
+            failed.extend(err.failed.into_iter().map(|i| positions[i]));
+        }
+        failed.sort_unstable();
+
+        BatchError::from_failed(failed)
+    }
+
+    /// The sender of the transaction, as recovered from the signature.
+    pub fn sender(&self) -> &Address {
+        &self.sender
+    }
+
+    /// The signature of the sender, or the ones of the owners of a multisig sender.
+    pub fn signatures(&self) -> &[Signature] {
+        match &self.witness {
+            Witness::Legacy(signature) | Witness::Single(signature) => {
+                std::slice::from_ref(signature)
+            }
+            Witness::Multisig { signatures, .. } => signatures,
+        }
+    }
+
+    /// The multisig account sending the transaction, if any.
+    pub fn multisig(&self) -> Option<&Multisig> {
+        match &self.witness {
+            Witness::Multisig { multisig, .. } => Some(multisig),
+            _ => None,
+        }
+    }
+
+    /// Encode the signed transaction into its canonical binary form.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.encode_into(&mut buf);
+        buf
+    }
+
+    /// Decode a signed transaction from its canonical binary form, recovering its sender.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+        let tx = Self::decode_from(&mut reader)?;
+        reader.finish()?;
+        Ok(tx)
+    }
+
+    /// Keccak-256 hash of the canonical binary form, which identifies the transaction.
+    pub fn hash(&self) -> B256 {
+        keccak256(self.encode())
+    }
+
+    fn encode_into(&self, buf: &mut Vec<u8>) {
+        match &self.witness {
+            Witness::Legacy(signature) => {
+                buf.push(SIGNED_TX_V0);
+                self.legacy().encode_into(buf);
+                buf.extend_from_slice(&signature.as_bytes());
+            }
+            Witness::Single(signature) => {
+                buf.push(SIGNED_TX_V1 | self.transaction.flags());
+                self.transaction.encode_into(buf);
+                buf.extend_from_slice(&signature.as_bytes());
+            }
+            Witness::Multisig {
+                multisig,
+                signatures,
+            } => {
+                buf.push(SIGNED_TX_MULTISIG | self.transaction.flags());
+                self.transaction.encode_into(buf);
+                multisig.encode_into(buf);
+                buf.push(signatures.len() as u8);
+                for signature in signatures {
+                    buf.extend_from_slice(&signature.as_bytes());
+                }
+            }
+        }
+    }
+
+    fn decode_from(reader: &mut Reader<'_>) -> Result<Self> {
+        let version = reader.u8()?;
+        let flags = version & (TIME_LOCKED | TYPED | CHAIN_BOUND);
+        match version & !flags {
+            SIGNED_TX_V0 if flags == 0 => {
+                let legacy = LegacyTransaction::decode_from(reader)?;
+                let signature = Signature::from_bytes(reader.take(65)?)?;
+                Self::from_legacy(legacy, signature)
+            }
+            SIGNED_TX_V1 => {
+                let transaction = Transaction::decode_from(reader, flags)?;
+                let signature = Signature::from_bytes(reader.take(65)?)?;
+                Self::new(transaction, signature)
+            }
+            SIGNED_TX_MULTISIG => {
+                let transaction = Transaction::decode_from(reader, flags)?;
+                let multisig = reader.multisig()?;
+                let signatures = (0..reader.u8()?)
+                    .map(|_| Signature::from_bytes(reader.take(65)?))
+                    .collect::<Result<Vec<_>>>()?;
+                // the binary form is canonical, the signatures cannot be reordered
+                if !signatures
+                    .windows(2)
+                    .all(|pair| pair[0].as_bytes() < pair[1].as_bytes())
+                {
+                    anyhow::bail!("Invalid transaction encoding: unsorted multisig signatures");
+                }
+                Self::new_multisig(transaction, multisig, signatures)
+            }
+            _ => anyhow::bail!("Unknown signed transaction version: {version:#04x}"),
+        }
+    }
+}
+
+/// JSON representation of a [`SignedTx`], the legacy format is told apart by its `"from"` field
+/// and the multisig one by its `"signatures"` field.
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SignedTxRepr {
+    Single {
+        transaction: AnyTransaction,
+        signature: Signature,
+    },
+    Multisig {
+        transaction: Transaction,
+        multisig: Multisig,
+        signatures: Vec<Signature>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum AnyTransaction {
+    Legacy(LegacyTransaction),
+    Current(Transaction),
+}
+
+impl TryFrom<SignedTxRepr> for SignedTx {
+    type Error = anyhow::Error;
+
+    fn try_from(repr: SignedTxRepr) -> Result<Self> {
+        match repr {
+            SignedTxRepr::Single {
+                transaction: AnyTransaction::Legacy(legacy),
+                signature,
+            } => Self::from_legacy(legacy, signature),
+            SignedTxRepr::Single {
+                transaction: AnyTransaction::Current(transaction),
+                signature,
+            } => Self::new(transaction, signature),
+            SignedTxRepr::Multisig {
+                transaction,
+                multisig,
+                signatures,
+            } => Self::new_multisig(transaction, multisig, signatures),
+        }
+    }
+}
+
+impl From<SignedTx> for SignedTxRepr {
+    fn from(tx: SignedTx) -> Self {
+        match tx.witness {
+            Witness::Legacy(signature) => SignedTxRepr::Single {
+                transaction: AnyTransaction::Legacy(tx.legacy()),
+                signature,
+            },
+            Witness::Single(signature) => SignedTxRepr::Single {
+                transaction: AnyTransaction::Current(tx.transaction),
+                signature,
+            },
+            Witness::Multisig {
+                multisig,
+                signatures,
+            } => SignedTxRepr::Multisig {
+                transaction: tx.transaction,
+                multisig,
+                signatures,
+            },
+        }
+    }
 }
 
 /// Define how a transection looks like in a block
+///
+/// The canonical binary form is the [`SignedTx`] one followed by the block related fields:
+/// ```text
+/// ┌───────────┬───────────┬───────────┬───────────┐
+/// │ signed tx │ timestamp │ gas_price │ gas_units │
+/// │           │    (8)    │    (8)    │    (8)    │
+/// └───────────┴───────────┴───────────┴───────────┘
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BlockTx {
     tx: SignedTx,
     pub timestamp: u64,
@@ -77,44 +902,225 @@ impl BlockTx {
     pub fn tx(&self) -> &Transaction {
         &self.tx.transaction
     }
+
+    pub fn sender(&self) -> &Address {
+        self.tx.sender()
+    }
+
+    pub fn signed_tx(&self) -> &SignedTx {
+        &self.tx
+    }
+
+    /// Encode the block transaction into its canonical binary form.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        self.tx.encode_into(&mut buf);
+        buf.extend_from_slice(&self.timestamp.to_be_bytes());
+        buf.extend_from_slice(&self.gas_price.to_be_bytes());
+        buf.extend_from_slice(&self.gas_units.to_be_bytes());
+        buf
+    }
+
+    /// Decode a block transaction from its canonical binary form, the signature is not verified.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(bytes);
+        let tx = BlockTx {
+            tx: SignedTx::decode_from(&mut reader)?,
+            timestamp: reader.u64()?,
+            gas_price: reader.u64()?,
+            gas_units: reader.u64()?,
+        };
+        reader.finish()?;
+        Ok(tx)
+    }
+}
+
+/// The Merkle tree over the canonical encodings of the transactions of a block, in order.
+pub fn tx_tree(txs: &[BlockTx]) -> MerkleTree {
+    MerkleTree::new(txs.iter().map(BlockTx::encode))
+}
+
+/// The transaction root of a block, the root of its [`tx_tree`].
+pub fn tx_root(txs: &[BlockTx]) -> B256 {
+    merkle_root(txs.iter().map(BlockTx::encode))
+}
+
+/// A cursor over the binary form of a transaction.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.bytes.len() < len {
+            anyhow::bail!(
+                "Invalid transaction encoding: expected {len} more bytes, got {}",
+                self.bytes.len()
+            );
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into()?)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.array::<1>()?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_be_bytes(self.array()?))
+    }
+
+    fn address(&mut self) -> Result<Address> {
+        Ok(Address::from(self.array::<20>()?))
+    }
+
+    /// length prefixed bytes
+    fn bytes(&mut self) -> Result<Vec<u8>> {
+        let len = self.u32()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    /// an optional [`TimeLock`], tagged by its kind
+    fn time_lock(&mut self) -> Result<Option<TimeLock>> {
+        match self.u8()? {
+            0 => Ok(None),
+            tag => Ok(Some(TimeLock::from_tag(tag, self.u64()?)?)),
+        }
+    }
+
+    /// a [`Multisig`] account, whose owners must be sorted
+    fn multisig(&mut self) -> Result<Multisig> {
+        let threshold = self.u8()?;
+        let owners = (0..self.u8()?)
+            .map(|_| self.address())
+            .collect::<Result<Vec<_>>>()?;
+        let multisig = Multisig::new(threshold, owners.clone())?;
+        if multisig.owners() != owners {
+            anyhow::bail!("Invalid transaction encoding: unsorted multisig owners");
+        }
+        Ok(multisig)
+    }
+
+    /// Ensure the whole input has been consumed.
+    fn finish(self) -> Result<()> {
+        if !self.bytes.is_empty() {
+            anyhow::bail!(
+                "Invalid transaction encoding: {} trailing bytes",
+                self.bytes.len()
+            );
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::database::transaction::Transaction;
+    use crate::database::multisig::Multisig;
+    use crate::database::timelock::TimeLock;
+    use crate::database::transaction::{BlockTx, SignedTx, Transaction, TxKind, tx_root, tx_tree};
+    use alloy_primitives::{hex, keccak256};
+    use bytelink_crypto::constant::SECP256K1N_ORDER;
+    use bytelink_crypto::signature::Signature;
     use bytelink_crypto::signer::Signer;
 
+    /// A legacy signed transaction, with the sender field, from the fixed alice key to bob.
+    /// Its signature is [`BASELINE_SIGNATURE`], made by the first transaction format.
+    const LEGACY_SIGNED_TX: &str = "00000000000000000719e7e376e7c213b7e7e7e46cc70a5dd086daff2a1563915e194d8cfba1943570603f7606a31155080000000000000064000000000000000100000008627974656c696e6b604d08cba64acf958060aefde09e4d4e08212c2bc1e5c8ac29937ce19f1662416d1185c94b69274d8f4217f12ecf0be26a37e42d34ef8ec87977c3208e5ff9f61b";
+
+    /// The JSON form of [`LEGACY_SIGNED_TX`].
+    const LEGACY_SIGNED_TX_JSON: &str = r#"{
+        "transaction": {
+            "nonce": 7,
+            "from": "0x19e7e376e7c213b7e7e7e46cc70a5dd086daff2a",
+            "to": "0x1563915e194d8cfba1943570603f7606a3115508",
+            "value": 100,
+            "tip": 1,
+            "data": "0x627974656c696e6b"
+        },
+        "signature": "0x604d08cba64acf958060aefde09e4d4e08212c2bc1e5c8ac29937ce19f1662416d1185c94b69274d8f4217f12ecf0be26a37e42d34ef8ec87977c3208e5ff9f61b"
+    }"#;
+
+    /// The message signed for [`LEGACY_SIGNED_TX`] by the first transaction format, whose JSON
+    /// form has the data payload as an array of bytes.
+    const BASELINE_MESSAGE: &str = r#"{"nonce":7,"from":"0x19e7e376e7c213b7e7e7e46cc70a5dd086daff2a","to":"0x1563915e194d8cfba1943570603f7606a3115508","value":100,"tip":1,"data":[98,121,116,101,108,105,110,107]}"#;
+
+    /// The signature of [`BASELINE_MESSAGE`] by alice, made by `Transaction::sign_by` of the
+    /// first transaction format.
+    const BASELINE_SIGNATURE: &str = "0x604d08cba64acf958060aefde09e4d4e08212c2bc1e5c8ac29937ce19f1662416d1185c94b69274d8f4217f12ecf0be26a37e42d34ef8ec87977c3208e5ff9f61b";
+
+    fn alice() -> Signer {
+        Signer::from_slice(&[0x11; 32]).unwrap()
+    }
+
+    fn bob() -> Signer {
+        Signer::from_slice(&[0x22; 32]).unwrap()
+    }
+
+    /// A signed transaction that is stable across runs, for snapshot tests.
+    fn fixed_signed_tx() -> SignedTx {
+        let tx = Transaction {
+            data: b"bytelink".to_vec(),
+            ..Transaction::new(7, bob().address(), 100, 1)
+        };
+
+        tx.sign_by(&alice()).unwrap()
+    }
+
+    /// A 2-of-3 multisig account owned by alice, bob and a random owner.
+    fn treasury() -> (Multisig, Signer) {
+        let carol = Signer::random();
+        let owners = vec![alice().address(), bob().address(), carol.address()];
+        (Multisig::new(2, owners).unwrap(), carol)
+    }
+
+    fn payout() -> Transaction {
+        Transaction::new(0, Signer::random().address(), 1_000, 1)
+    }
+
+    fn fixed_block_tx() -> BlockTx {
+        let mut tx = BlockTx::new(fixed_signed_tx(), 15, 21);
+        tx.timestamp = 1_639_699_200_000;
+        tx
+    }
+
     #[test]
     pub fn naive_valid_tx() {
         let alice = Signer::random();
         let bob = Signer::random();
 
-        let tx = Transaction {
-            nonce: 0,
-            from: alice.address(),
-            to: bob.address(),
-            value: 100,
-            tip: 1,
-            data: vec![],
-        };
+        let tx = Transaction::new(0, bob.address(), 100, 1);
 
         let signed_tx = tx.sign_by(&alice).unwrap();
 
         assert!(signed_tx.verify().is_ok());
+        assert_eq!(signed_tx.sender(), &alice.address());
+    }
+
+    #[tokio::test]
+    async fn async_signing_matches_sync_signing() {
+        let tx = fixed_signed_tx().transaction;
+        let signed_tx = tx.sign_by_async(&alice()).await.unwrap();
+        assert_eq!(signed_tx, fixed_signed_tx());
     }
 
     #[test]
     pub fn send_to_self_invalid_tx() {
         let alice = Signer::random();
 
-        let tx = Transaction {
-            nonce: 0,
-            from: alice.address(),
-            to: alice.address(),
-            value: 100,
-            tip: 1,
-            data: vec![],
-        };
+        let tx = Transaction::new(0, alice.address(), 100, 1);
 
         let signed_tx = tx.sign_by(&alice).unwrap();
 
@@ -125,19 +1131,435 @@ mod tests {
     pub fn bad_signature_invalid_tx() {
         let alice = Signer::random();
         let bob = Signer::random();
-        let charlie = Signer::random();
 
-        let tx = Transaction {
-            nonce: 0,
-            from: alice.address(),
-            to: bob.address(),
-            value: 100,
-            tip: 1,
-            data: vec![],
-        };
+        let tx = Transaction::new(0, bob.address(), 100, 1);
 
-        let signed_tx = tx.sign_by(&charlie).unwrap();
+        let mut signed_tx = tx.sign_by(&alice).unwrap();
+        // tamper with the transaction after signing, the signature now recovers another address
+        signed_tx.transaction.value = 1_000_000;
 
         assert!(signed_tx.verify().is_err());
     }
+
+    #[test]
+    fn high_s_invalid_tx() {
+        let signed_tx = fixed_signed_tx();
+        assert!(signed_tx.verify().is_ok());
+
+        // replace the signature by its high-s twin (r, n - s, !v), which recovers the same sender
+        let signature = &signed_tx.signatures()[0];
+        let mut bytes = signature.as_bytes();
+        bytes[32..64].copy_from_slice(&(SECP256K1N_ORDER - signature.s()).to_be_bytes::<32>());
+        bytes[64] = if signature.v() { 27 } else { 28 };
+        let twin = Signature::from_bytes(&bytes).unwrap();
+        assert_eq!(twin.normalized_s(), *signature);
+
+        let malleated = SignedTx::new(signed_tx.transaction.clone(), twin).unwrap();
+        assert_eq!(malleated.sender(), signed_tx.sender());
+        assert_ne!(malleated.hash(), signed_tx.hash());
+        assert!(malleated.verify().is_err());
+    }
+
+    #[test]
+    fn verify_batch_reports_invalid_txs() {
+        let alice = Signer::random();
+        let mut txs = (0..8)
+            .map(|nonce| {
+                let tx = Transaction::new(nonce, bob().address(), 100, 1);
+                tx.sign_by(&alice).unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert!(SignedTx::verify_batch(&txs).is_ok());
+
+        // tampered after signing
+        txs[2].transaction.value = 1_000_000;
+        // sent to self
+        txs[5] = Transaction::new(5, alice.address(), 100, 1)
+            .sign_by(&alice)
+            .unwrap();
+
+        let err = SignedTx::verify_batch(&txs).unwrap_err();
+        assert_eq!(err.failed, vec![2, 5]);
+        for (index, tx) in txs.iter().enumerate() {
+            assert_eq!(tx.verify().is_err(), err.failed.contains(&index));
+        }
+    }
+
+    #[test]
+    fn signed_tx_json() {
+        let signed_tx = fixed_signed_tx();
+        insta::assert_json_snapshot!(signed_tx);
+
+        let json = serde_json::to_string(&signed_tx).unwrap();
+        let decoded: SignedTx = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, signed_tx);
+        assert!(decoded.verify().is_ok());
+    }
+
+    #[test]
+    fn block_tx_json() {
+        let block_tx = fixed_block_tx();
+        insta::assert_json_snapshot!(block_tx);
+
+        let json = serde_json::to_string(&block_tx).unwrap();
+        let decoded: BlockTx = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, block_tx);
+    }
+
+    #[test]
+    fn signed_tx_binary() {
+        let signed_tx = fixed_signed_tx();
+        let encoded = signed_tx.encode();
+        insta::assert_snapshot!(hex::encode(&encoded));
+
+        let decoded = SignedTx::decode(&encoded).unwrap();
+        assert_eq!(decoded, signed_tx);
+        assert_eq!(decoded.sender(), &alice().address());
+        assert!(decoded.verify().is_ok());
+        assert_eq!(decoded.hash(), signed_tx.hash());
+    }
+
+    #[test]
+    fn block_tx_binary() {
+        let block_tx = fixed_block_tx();
+        let encoded = block_tx.encode();
+        insta::assert_snapshot!(hex::encode(&encoded));
+
+        let decoded = BlockTx::decode(&encoded).unwrap();
+        assert_eq!(decoded, block_tx);
+    }
+
+    #[test]
+    fn block_tx_root() {
+        let payout = BlockTx::new(payout().sign_by(&bob()).unwrap(), 1, 21);
+        let txs = [fixed_block_tx(), payout];
+        let root = tx_root(&txs);
+        assert_eq!(root, tx_tree(&txs).root());
+
+        let proof = tx_tree(&txs).proof(1).unwrap();
+        assert!(proof.verify(&root, txs[1].encode()));
+        assert!(!proof.verify(&root, txs[0].encode()));
+
+        // the root commits to the order of the transactions
+        let reordered = [txs[1].clone(), txs[0].clone()];
+        assert_ne!(tx_root(&reordered), root);
+    }
+
+    #[test]
+    fn sign_data_as_byte_array() {
+        let signature: Signature = BASELINE_SIGNATURE.parse().unwrap();
+        let signer = signature
+            .recover_address_from_msg(BASELINE_MESSAGE.as_bytes())
+            .unwrap();
+        assert_eq!(signer, alice().address());
+
+        // the sender aside, the signed layout is unchanged, whatever the JSON form of the API
+        let message = BASELINE_MESSAGE.replace(
+            r#""from":"0x19e7e376e7c213b7e7e7e46cc70a5dd086daff2a","#,
+            "",
+        );
+        let transaction = fixed_signed_tx().transaction;
+        assert_eq!(transaction.signing_message().unwrap(), message.as_bytes());
+        assert!(
+            serde_json::to_string(&transaction)
+                .unwrap()
+                .contains(r#""data":"0x"#)
+        );
+    }
+
+    #[test]
+    fn sender_is_not_encoded() {
+        let legacy = hex::decode(LEGACY_SIGNED_TX).unwrap();
+        assert_eq!(fixed_signed_tx().encode().len() + 20, legacy.len());
+    }
+
+    #[test]
+    fn decode_legacy_binary() {
+        let bytes = hex::decode(LEGACY_SIGNED_TX).unwrap();
+        let legacy = SignedTx::decode(&bytes).unwrap();
+
+        assert_eq!(legacy.sender(), &alice().address());
+        assert_eq!(legacy.transaction, fixed_signed_tx().transaction);
+        assert!(legacy.verify().is_ok());
+        // re-encoding keeps the legacy format, as the signature covers it
+        assert_eq!(legacy.encode(), bytes);
+    }
+
+    #[test]
+    fn verify_baseline_signed_tx() {
+        let legacy = SignedTx::decode(&hex::decode(LEGACY_SIGNED_TX).unwrap()).unwrap();
+        assert_eq!(legacy.signatures()[0].to_string(), BASELINE_SIGNATURE);
+        assert_eq!(
+            legacy.signing_message().unwrap(),
+            BASELINE_MESSAGE.as_bytes(),
+            "the legacy message is the one signed by the first format"
+        );
+        assert!(legacy.verify().is_ok());
+    }
+
+    #[test]
+    fn decode_legacy_json() {
+        let legacy: SignedTx = serde_json::from_str(LEGACY_SIGNED_TX_JSON).unwrap();
+
+        assert_eq!(legacy.sender(), &alice().address());
+        assert!(legacy.verify().is_ok());
+        assert_eq!(
+            legacy.encode(),
+            hex::decode(LEGACY_SIGNED_TX).unwrap(),
+            "JSON and binary legacy forms describe the same transaction"
+        );
+
+        let json = serde_json::to_value(&legacy).unwrap();
+        assert!(json["transaction"]["from"].is_string());
+    }
+
+    #[test]
+    fn reject_legacy_with_wrong_sender() {
+        // replace the sender of the legacy transaction by bob
+        let mut bytes = hex::decode(LEGACY_SIGNED_TX).unwrap();
+        bytes[9..29].copy_from_slice(bob().address().as_bytes());
+        assert!(SignedTx::decode(&bytes).is_err());
+
+        let json = LEGACY_SIGNED_TX_JSON.replace(
+            "0x19e7e376e7c213b7e7e7e46cc70a5dd086daff2a",
+            &format!("{:x}", bob().address()),
+        );
+        assert!(serde_json::from_str::<SignedTx>(&json).is_err());
+    }
+
+    #[test]
+    fn multisig_threshold() {
+        let (multisig, carol) = treasury();
+        let tx = payout();
+
+        let partial = tx
+            .clone()
+            .sign_multisig(multisig.clone(), &alice())
+            .unwrap();
+        assert_eq!(partial.sender(), &multisig.address());
+        assert!(partial.verify().is_err(), "1 of 2 signatures");
+
+        let other = tx.sign_multisig(multisig.clone(), &carol).unwrap();
+        let signed_tx = partial.clone().merge(&other).unwrap();
+        assert_eq!(signed_tx.signatures().len(), 2);
+        assert!(signed_tx.verify().is_ok());
+        assert!(SignedTx::verify_batch([&signed_tx, &partial]).is_err());
+
+        // merging is commutative and idempotent
+        let reversed = other.merge(&partial).unwrap();
+        assert_eq!(reversed, signed_tx);
+        assert_eq!(signed_tx.clone().merge(&partial).unwrap(), signed_tx);
+
+        // tampered after signing
+        let mut tampered = signed_tx.clone();
+        tampered.transaction.value = 1_000_000;
+        assert!(tampered.verify().is_err());
+        let err = SignedTx::verify_batch([&signed_tx, &tampered]).unwrap_err();
+        assert_eq!(err.failed, vec![1]);
+    }
+
+    #[test]
+    fn multisig_rejects_foreign_signatures() {
+        let (multisig, _) = treasury();
+        let tx = payout();
+
+        // not an owner
+        assert!(
+            tx.clone()
+                .sign_multisig(multisig.clone(), &Signer::random())
+                .is_err()
+        );
+
+        // the signature of an owner over the plain transaction is not an approval
+        let plain = tx.clone().sign_by(&alice()).unwrap().signatures()[0];
+        let approval = tx.clone().sign_multisig(multisig.clone(), &bob()).unwrap();
+        let replayed = SignedTx::new_multisig(
+            tx.clone(),
+            multisig.clone(),
+            vec![plain, approval.signatures()[0]],
+        )
+        .unwrap();
+        assert!(replayed.verify().is_err());
+
+        // nor is an approval for another account of the same owners
+        let (other, _) = treasury();
+        let elsewhere = tx.clone().sign_multisig(other, &alice()).unwrap();
+        let replayed = SignedTx::new_multisig(
+            tx.clone(),
+            multisig.clone(),
+            vec![elsewhere.signatures()[0], approval.signatures()[0]],
+        )
+        .unwrap();
+        assert!(replayed.verify().is_err());
+
+        // signatures of another transaction cannot be merged
+        let mut different = tx.clone();
+        different.nonce = 1;
+        let different = different.sign_multisig(multisig, &alice()).unwrap();
+        assert!(approval.merge(&different).is_err());
+        assert!(fixed_signed_tx().merge(&different).is_err());
+    }
+
+    #[test]
+    fn multisig_tx_encoding() {
+        let (multisig, carol) = treasury();
+        let tx = payout();
+        let signed_tx = tx
+            .clone()
+            .sign_multisig(multisig.clone(), &bob())
+            .unwrap()
+            .merge(&tx.sign_multisig(multisig.clone(), &carol).unwrap())
+            .unwrap();
+        assert!(signed_tx.verify().is_ok());
+
+        let encoded = signed_tx.encode();
+        assert_eq!(encoded[0], 0x02);
+        let decoded = SignedTx::decode(&encoded).unwrap();
+        assert_eq!(decoded, signed_tx);
+        assert_eq!(decoded.multisig(), Some(&multisig));
+        assert!(decoded.verify().is_ok());
+
+        let json = serde_json::to_value(&signed_tx).unwrap();
+        assert_eq!(json["signatures"].as_array().unwrap().len(), 2);
+        assert_eq!(json["multisig"]["threshold"], 2);
+        let decoded: SignedTx = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, signed_tx);
+
+        // the signatures cannot be reordered
+        let signatures_at = encoded.len() - 2 * 65;
+        let mut reordered = encoded[..signatures_at].to_vec();
+        reordered.extend_from_slice(&encoded[signatures_at + 65..]);
+        reordered.extend_from_slice(&encoded[signatures_at..signatures_at + 65]);
+        assert!(SignedTx::decode(&reordered).is_err());
+    }
+
+    #[test]
+    fn time_locked_tx() {
+        let mut tx = fixed_signed_tx().transaction;
+        tx.valid_after = Some(TimeLock::Height(100));
+        tx.valid_until = Some(TimeLock::Timestamp(1_767_225_600_000));
+        let signed_tx = tx.sign_by(&alice()).unwrap();
+        assert!(signed_tx.verify().is_ok());
+
+        let encoded = signed_tx.encode();
+        assert_eq!(encoded[0], 0x81);
+        assert_eq!(encoded.len(), fixed_signed_tx().encode().len() + 2 * 9);
+        assert_eq!(SignedTx::decode(&encoded).unwrap(), signed_tx);
+
+        let json = serde_json::to_value(&signed_tx).unwrap();
+        assert_eq!(json["transaction"]["valid_after"]["height"], 100);
+        let decoded: SignedTx = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, signed_tx);
+
+        // the time locks are covered by the signature
+        let mut tampered = signed_tx.clone();
+        tampered.transaction.valid_after = None;
+        assert!(tampered.verify().is_err());
+
+        // flagged as time-locked without time lock
+        let mut bytes = fixed_signed_tx().encode();
+        bytes[0] |= 0x80;
+        bytes.splice(bytes.len() - 65..bytes.len() - 65, [0, 0]);
+        assert!(SignedTx::decode(&bytes).is_err());
+    }
+
+    #[test]
+    fn typed_tx_encoding() {
+        let kinds = [
+            TxKind::Create,
+            TxKind::CreateToken { supply: 1_000 },
+            TxKind::TokenTransfer {
+                token: bob().address(),
+                amount: 10,
+            },
+            TxKind::TokenApprove {
+                token: bob().address(),
+                amount: 10,
+            },
+            TxKind::TokenTransferFrom {
+                token: bob().address(),
+                from: alice().address(),
+                amount: 10,
+            },
+            TxKind::HtlcLock {
+                hashlock: keccak256(b"secret"),
+                timeout: TimeLock::Timestamp(1_767_225_600_000),
+            },
+            TxKind::HtlcClaim,
+            TxKind::HtlcRefund,
+        ];
+        for kind in kinds {
+            let mut tx = fixed_signed_tx().transaction;
+            tx.kind = kind;
+            tx.valid_until = Some(TimeLock::Height(100));
+            let signed_tx = tx.sign_by(&alice()).unwrap();
+
+            let encoded = signed_tx.encode();
+            assert_eq!(encoded[0], 0xc1);
+            assert_eq!(SignedTx::decode(&encoded).unwrap(), signed_tx);
+            let json = serde_json::to_string(&signed_tx).unwrap();
+            assert_eq!(serde_json::from_str::<SignedTx>(&json).unwrap(), signed_tx);
+        }
+
+        let mut tx = fixed_signed_tx().transaction;
+        tx.kind = TxKind::CreateToken { supply: 7 };
+        let json = serde_json::to_value(&tx).unwrap();
+        assert_eq!(json["kind"]["create_token"]["supply"], 7);
+
+        // a transfer is never tagged
+        let mut bytes = fixed_signed_tx().encode();
+        bytes[0] |= 0x40;
+        bytes.insert(bytes.len() - 65, 0);
+        assert!(SignedTx::decode(&bytes).is_err());
+    }
+
+    #[test]
+    fn chain_bound_tx() {
+        let mut tx = fixed_signed_tx().transaction;
+        tx.chain_id = Some(7);
+        let signed_tx = tx.sign_by(&alice()).unwrap();
+
+        let encoded = signed_tx.encode();
+        assert_eq!(encoded[0], 0x21);
+        assert_eq!(encoded.len(), fixed_signed_tx().encode().len() + 2);
+        assert_eq!(SignedTx::decode(&encoded).unwrap(), signed_tx);
+
+        let json = serde_json::to_value(&signed_tx).unwrap();
+        assert_eq!(json["transaction"]["chain_id"], 7);
+        assert_eq!(serde_json::from_value::<SignedTx>(json).unwrap(), signed_tx);
+
+        // the chain id is covered by the signature
+        let mut replayed = signed_tx.clone();
+        replayed.transaction.chain_id = Some(8);
+        assert!(replayed.verify().is_err());
+    }
+
+    #[test]
+    fn reject_invalid_time_locks() {
+        let mut tx = fixed_signed_tx().transaction;
+        tx.valid_after = Some(TimeLock::Height(100));
+        tx.valid_until = Some(TimeLock::Height(99));
+        assert!(tx.sign_by(&alice()).unwrap().verify().is_err());
+
+        // the legacy message does not cover time locks
+        let mut legacy = SignedTx::decode(&hex::decode(LEGACY_SIGNED_TX).unwrap()).unwrap();
+        legacy.transaction.valid_until = Some(TimeLock::Height(1));
+        assert!(legacy.verify().is_err());
+    }
+
+    #[test]
+    fn reject_malformed_binary() {
+        let encoded = fixed_block_tx().encode();
+
+        // truncated input
+        assert!(BlockTx::decode(&encoded[..encoded.len() - 1]).is_err());
+        // trailing bytes
+        assert!(BlockTx::decode(&[encoded.as_slice(), &[0]].concat()).is_err());
+        // unknown version
+        let mut unknown = encoded.clone();
+        unknown[0] = 0xff;
+        assert!(BlockTx::decode(&unknown).is_err());
+        // block tx is not a signed tx
+        assert!(SignedTx::decode(&encoded).is_err());
+    }
 }
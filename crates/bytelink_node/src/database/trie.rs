@@ -0,0 +1,275 @@
+use crate::database::account::Account;
+use crate::database::htlc::Htlc;
+use crate::database::token::Token;
+use alloy_primitives::{B256, keccak256};
+use anyhow::Result;
+use bytelink_crypto::address::Address;
+use bytelink_crypto::smt::{SparseMerkleProof, SparseMerkleTree};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Position of an account in the state trie, which is the Keccak-256 hash of its address.
+fn trie_key(address: &Address) -> B256 {
+    keccak256(address.as_bytes())
+}
+
+/// Position of a record other than an account in the state trie, the Keccak-256 hash of a
+/// tag naming the kind of record followed by its key, so it never collides with an account.
+fn tagged_key(tag: &[u8], parts: &[&[u8]]) -> B256 {
+    let mut buf = tag.to_vec();
+    for part in parts {
+        buf.extend_from_slice(part);
+    }
+    keccak256(buf)
+}
+
+/// A piece of the state committed to the state trie, used to track what changed since the
+/// last commit.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum StateKey {
+    Account(Address),
+    /// the code hash and storage root of a contract account
+    Contract(Address),
+    /// a storage word of a contract account, committed to its storage trie
+    Storage(Address, u64),
+    Token(Address),
+    /// a token balance, by `(token id, holder)`
+    TokenBalance(Address, Address),
+    /// a token allowance, by `(token id, owner, spender)`
+    Allowance(Address, Address, Address),
+    /// a pending HTLC, holding its funds in escrow
+    Htlc(Address),
+}
+
+/// Authenticated commitment to the state, whose root is the state root of the chain.
+///
+/// Accounts are stored at the hash of their address. Every contract has a leaf of its own,
+/// holding the hash of its code and the root of a storage trie of its words. Tokens, token
+/// balances, allowances and pending HTLCs are stored at the hash of a tag followed by their
+/// key, such as `keccak("balance" ‖ token ‖ holder)`:
+/// ```text
+///                        state root
+///                      /            \
+///        keccak(address)            keccak("contract" ‖ address)
+///              │                                │
+///        account hash       keccak(code hash ‖ storage root)
+///                                               │
+///                                storage trie: keccak(key) -> keccak(word)
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StateTrie {
+    tree: SparseMerkleTree,
+    /// the storage trie of every contract with storage
+    storage: HashMap<Address, SparseMerkleTree>,
+}
+
+impl StateTrie {
+    pub fn new() -> Self {
+        StateTrie {
+            tree: SparseMerkleTree::new(),
+            storage: HashMap::new(),
+        }
+    }
+
+    pub fn root(&self) -> B256 {
+        self.tree.root()
+    }
+
+    /// Update the account stored at `address`, `None` removes it. Returns the new state root.
+    pub fn update(&mut self, address: &Address, account: Option<&Account>) -> B256 {
+        let key = trie_key(address);
+        match account {
+            Some(account) => self.tree.insert(&key, &account.hash()),
+            None => self.tree.remove(&key),
+        }
+    }
+
+    /// Update a storage word of a contract, zero removes it. The leaf of the contract is
+    /// only updated by the next [`update_contract`][StateTrie::update_contract].
+    pub fn update_storage(&mut self, address: &Address, key: u64, word: u64) {
+        let slot = keccak256(key.to_be_bytes());
+        let value = (word != 0).then(|| keccak256(word.to_be_bytes()));
+        let tree = self.storage.entry(address.clone()).or_default();
+        tree.update(&slot, value.as_ref());
+        if tree.root() == SparseMerkleTree::empty_root() {
+            self.storage.remove(address);
+        }
+    }
+
+    /// The root of the storage trie of a contract.
+    pub fn storage_root(&self, address: &Address) -> B256 {
+        self.storage
+            .get(address)
+            .map_or_else(SparseMerkleTree::empty_root, SparseMerkleTree::root)
+    }
+
+    /// Update the leaf of a contract from its code and current storage root, `None` for an
+    /// address without code. Returns the new state root.
+    pub fn update_contract(&mut self, address: &Address, code: Option<&[u8]>) -> B256 {
+        let key = tagged_key(b"contract", &[address.as_bytes()]);
+        let storage_root = self.storage_root(address);
+        if code.is_none() && storage_root == SparseMerkleTree::empty_root() {
+            return self.tree.remove(&key);
+        }
+        let code_hash = keccak256(code.unwrap_or_default());
+        let leaf = keccak256([code_hash.as_slice(), storage_root.as_slice()].concat());
+        self.tree.insert(&key, &leaf)
+    }
+
+    /// Update a token, `None` removes it. Returns the new state root.
+    pub fn update_token(&mut self, id: &Address, token: Option<&Token>) -> B256 {
+        let key = tagged_key(b"token", &[id.as_bytes()]);
+        self.tree.update(&key, token.map(Token::hash).as_ref())
+    }
+
+    /// Update the balance of `holder` in a token, zero removes it. Returns the new state root.
+    pub fn update_token_balance(
+        &mut self,
+        token: &Address,
+        holder: &Address,
+        balance: u64,
+    ) -> B256 {
+        let key = tagged_key(b"balance", &[token.as_bytes(), holder.as_bytes()]);
+        self.update_amount(&key, balance)
+    }
+
+    /// Update the allowance of `spender` over the token of `owner`, zero removes it.
+    /// Returns the new state root.
+    pub fn update_allowance(
+        &mut self,
+        token: &Address,
+        owner: &Address,
+        spender: &Address,
+        amount: u64,
+    ) -> B256 {
+        let parts: [&[u8]; 3] = [token.as_bytes(), owner.as_bytes(), spender.as_bytes()];
+        let key = tagged_key(b"allowance", &parts);
+        self.update_amount(&key, amount)
+    }
+
+    /// Update a pending HTLC, `None` once settled. Returns the new state root.
+    pub fn update_htlc(&mut self, id: &Address, htlc: Option<&Htlc>) -> B256 {
+        let key = tagged_key(b"htlc", &[id.as_bytes()]);
+        self.tree.update(&key, htlc.map(Htlc::hash).as_ref())
+    }
+
+    fn update_amount(&mut self, key: &B256, amount: u64) -> B256 {
+        let value = (amount != 0).then(|| keccak256(amount.to_be_bytes()));
+        self.tree.update(key, value.as_ref())
+    }
+
+    /// Prove that `address` holds `account` (or nothing if `None`) under the current root.
+    ///
+    /// The caller is responsible for passing the account currently committed in the trie,
+    /// otherwise the proof will not verify.
+    pub fn prove(&self, address: &Address, account: Option<Account>) -> AccountProof {
+        AccountProof {
+            address: address.clone(),
+            account,
+            proof: self.tree.proof(&trie_key(address)),
+        }
+    }
+}
+
+/// A proof of the state of an account, or of its absence, against a state root.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountProof {
+    pub address: Address,
+    pub account: Option<Account>,
+    pub proof: SparseMerkleProof,
+}
+
+impl AccountProof {
+    pub fn verify(&self, state_root: &B256) -> Result<()> {
+        if let Some(account) = &self.account
+            && account.address() != &self.address
+        {
+            anyhow::bail!("Invalid account proof: account does not belong to the proven address");
+        }
+
+        let key = trie_key(&self.address);
+        let value = self.account.as_ref().map(Account::hash);
+        if !self.proof.verify(state_root, &key, value.as_ref()) {
+            anyhow::bail!("Invalid account proof: does not match the state root");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::account::Account;
+    use crate::database::trie::StateTrie;
+    use bytelink_crypto::address::Address;
+    use bytelink_crypto::smt::SparseMerkleTree;
+    use rand::rng;
+
+    #[test]
+    fn empty_state_root() {
+        assert_eq!(StateTrie::new().root(), SparseMerkleTree::empty_root());
+    }
+
+    #[test]
+    fn prove_account() {
+        let mut trie = StateTrie::new();
+        let alice = Account::new(Address::random(rng()), 100);
+        let bob = Account::new(Address::random(rng()), 200);
+        trie.update(alice.address(), Some(&alice));
+        trie.update(bob.address(), Some(&bob));
+
+        let proof = trie.prove(alice.address(), Some(alice.clone()));
+        assert!(proof.verify(&trie.root()).is_ok());
+
+        // lie about the balance of alice
+        let forged = trie.prove(
+            alice.address(),
+            Some(Account::new(alice.address().clone(), 1)),
+        );
+        assert!(forged.verify(&trie.root()).is_err());
+
+        // claim bob's account is at alice's address
+        let swapped = trie.prove(alice.address(), Some(bob.clone()));
+        assert!(swapped.verify(&trie.root()).is_err());
+    }
+
+    #[test]
+    fn prove_missing_account() {
+        let mut trie = StateTrie::new();
+        let alice = Account::new(Address::random(rng()), 100);
+        trie.update(alice.address(), Some(&alice));
+
+        let charlie = Address::random(rng());
+        let proof = trie.prove(&charlie, None);
+        assert!(proof.verify(&trie.root()).is_ok());
+
+        let absent_alice = trie.prove(alice.address(), None);
+        assert!(absent_alice.verify(&trie.root()).is_err());
+    }
+
+    #[test]
+    fn commit_contract_storage() {
+        let mut trie = StateTrie::new();
+        let contract = Address::random(rng());
+        let empty = trie.root();
+
+        trie.update_contract(&contract, Some(b"code"));
+        let deployed = trie.root();
+        assert_ne!(deployed, empty);
+
+        trie.update_storage(&contract, 1, 42);
+        assert_eq!(
+            trie.root(),
+            deployed,
+            "pending until the contract is updated"
+        );
+        let written = trie.update_contract(&contract, Some(b"code"));
+        assert_ne!(written, deployed);
+        assert_ne!(trie.storage_root(&contract), SparseMerkleTree::empty_root());
+
+        // clearing the word restores the previous root
+        trie.update_storage(&contract, 1, 0);
+        assert_eq!(trie.update_contract(&contract, Some(b"code")), deployed);
+        assert_eq!(trie.update_contract(&contract, None), empty);
+    }
+}
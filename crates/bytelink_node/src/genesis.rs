@@ -8,7 +8,7 @@ use std::path::Path;
 pub struct Genesis {
     date: Timestamp,
     /// The unique identifier for this running instance of the blockchain.
-    chain_id: u16,
+    pub chain_id: u16,
     /// The maximum number of transactions allowed in each block.
     transections_per_block: u16,
     /// The difficulty level for mining new blocks.
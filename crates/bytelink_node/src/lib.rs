@@ -2,3 +2,4 @@ pub mod database;
 pub mod genesis;
 pub mod mempool;
 pub mod state;
+pub mod vm;
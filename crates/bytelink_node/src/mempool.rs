@@ -1,14 +1,29 @@
-mod fcfs;
-mod selector;
+pub mod fcfs;
+pub mod selector;
 
-use crate::database::transaction::BlockTx;
+use crate::database::timelock::{BlockInfo, TimeLock, Validity};
+use crate::database::transaction::{BlockTx, SignedTx};
 use anyhow::Result;
 use bytelink_crypto::address::Address;
+use bytelink_crypto::batch::BatchError;
 use dashmap::DashMap;
+use std::sync::RwLock;
+
+/// How many blocks past the next one a transaction can be held for, at most.
+pub const MAX_HOLD_HEIGHT: u64 = 100_000;
+
+/// How long past the next block a transaction can be held for, at most, in milliseconds.
+pub const MAX_HOLD_MILLIS: u64 = 30 * 24 * 60 * 60 * 1000;
 
 /// A mempool is staging area for unconfirmed transactions in a blockchain.
+///
+/// Time-locked transactions that are not valid yet are held in the pool until the
+/// next block reaches their [`valid_after`][crate::database::transaction::Transaction::valid_after],
+/// as long as it is within [`MAX_HOLD_HEIGHT`] blocks or [`MAX_HOLD_MILLIS`] of the next block.
 pub struct MemPool {
     pool: DashMap<Key, BlockTx>,
+    /// the block being built, against which time locks are checked
+    next_block: RwLock<BlockInfo>,
 }
 
 #[derive(PartialEq, Eq, Hash, Clone)]
@@ -34,6 +49,7 @@ impl MemPool {
     pub fn new() -> Self {
         MemPool {
             pool: DashMap::new(),
+            next_block: RwLock::new(BlockInfo::default()),
         }
     }
 
@@ -42,9 +58,37 @@ impl MemPool {
         self.pool.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.pool.is_empty()
+    }
+
+    /// Admit a transaction once its signature is verified.
     pub fn upsert(&self, tx: BlockTx) -> Result<()> {
+        tx.signed_tx().verify()?;
+        self.insert(tx)
+    }
+
+    /// Insert a transaction whose signature is already verified.
+    fn insert(&self, tx: BlockTx) -> Result<()> {
         // use address with nonce as the key
-        let key = Key::new(tx.tx().from.clone(), tx.tx().nonce);
+        let key = Key::new(tx.sender().clone(), tx.tx().nonce);
+
+        let next_block = self.next_block();
+        if tx.tx().validity(&next_block) == Validity::Expired {
+            return Err(anyhow::anyhow!("transaction expired"));
+        }
+        let too_far = match tx.tx().valid_after {
+            Some(TimeLock::Height(height)) => {
+                height > next_block.height.saturating_add(MAX_HOLD_HEIGHT)
+            }
+            Some(TimeLock::Timestamp(timestamp)) => {
+                timestamp > next_block.timestamp.saturating_add(MAX_HOLD_MILLIS)
+            }
+            None => false,
+        };
+        if too_far {
+            return Err(anyhow::anyhow!("transaction held too far in the future"));
+        }
 
         if let Some(entry) = self.pool.get(&key) {
             // ethereum require 10% bump in the tip to replace an existing tx
@@ -60,6 +104,73 @@ impl MemPool {
         Ok(())
     }
 
+    /// Admit a burst of transactions, verifying their signatures in parallel.
+    /// Valid transactions are inserted as with [`upsert`][MemPool::upsert],
+    /// the position of the invalid or rejected ones is reported.
+    pub fn upsert_batch(&self, txs: Vec<BlockTx>) -> std::result::Result<(), BatchError> {
+        let invalid = match SignedTx::verify_batch(txs.iter().map(BlockTx::signed_tx)) {
+            Ok(()) => Vec::new(),
+            Err(err) => err.failed,
+        };
+
+        let mut failed = Vec::with_capacity(invalid.len());
+        let mut invalid = invalid.into_iter().peekable();
+        for (index, tx) in txs.into_iter().enumerate() {
+            if invalid.next_if_eq(&index).is_some() || self.insert(tx).is_err() {
+                failed.push(index);
+            }
+        }
+
+        BatchError::from_failed(failed)
+    }
+
+    /// The block being built, as of the last [`advance`][MemPool::advance].
+    pub fn next_block(&self) -> BlockInfo {
+        *self.next_block.read().expect("mempool lock poisoned")
+    }
+
+    /// Move on to the next block to build, dropping the transactions that expired.
+    pub fn advance(&self, block: BlockInfo) {
+        *self.next_block.write().expect("mempool lock poisoned") = block;
+        self.pool
+            .retain(|_, tx| tx.tx().validity(&block) != Validity::Expired);
+    }
+
+    /// The transactions that can be included in the next block, by sender then nonce.
+    ///
+    /// The transactions of a sender are ready in nonce order, up to the first one that is held
+    /// until it becomes valid, or missing from the pool: the later ones cannot be included
+    /// before it.
+    pub fn ready(&self) -> Vec<BlockTx> {
+        let block = self.next_block();
+        let mut txs: Vec<_> = self.pool.iter().map(|tx| tx.value().clone()).collect();
+        txs.sort_unstable_by(|a, b| {
+            let key = |tx: &BlockTx| (*tx.sender().as_bytes(), tx.tx().nonce);
+            key(a).cmp(&key(b))
+        });
+
+        let mut ready: Vec<BlockTx> = Vec::with_capacity(txs.len());
+        // the sender and nonce of the previous transaction, while its sender is not blocked
+        let mut previous: Option<(Address, u64)> = None;
+        let mut blocked: Option<Address> = None;
+        for tx in txs {
+            if blocked.as_ref() == Some(tx.sender()) {
+                continue;
+            }
+            let follows = match &previous {
+                Some((sender, nonce)) if sender == tx.sender() => tx.tx().nonce == nonce + 1,
+                _ => true,
+            };
+            if !follows || tx.tx().validity(&block) != Validity::Valid {
+                blocked = Some(tx.sender().clone());
+                continue;
+            }
+            previous = Some((tx.sender().clone(), tx.tx().nonce));
+            ready.push(tx);
+        }
+        ready
+    }
+
     pub fn delete(&self, key: &Key) {
         self.pool.remove(key);
     }
@@ -68,3 +179,157 @@ impl MemPool {
         self.pool.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::database::timelock::{BlockInfo, TimeLock};
+    use crate::database::transaction::{BlockTx, Transaction};
+    use crate::mempool::{MAX_HOLD_HEIGHT, MAX_HOLD_MILLIS, MemPool};
+    use bytelink_crypto::signer::Signer;
+
+    fn block_tx(signer: &Signer, nonce: u64, tip: u64) -> BlockTx {
+        let tx = Transaction::new(nonce, Signer::random().address(), 100, tip);
+        BlockTx::new(tx.sign_by(signer).unwrap(), 15, 21)
+    }
+
+    #[test]
+    fn upsert_verifies_signature() {
+        let alice = Signer::random();
+        let pool = MemPool::new();
+        pool.upsert(block_tx(&alice, 0, 10)).unwrap();
+
+        // signed by alice but sent to alice
+        let mut tx = block_tx(&alice, 1, 10).tx().clone();
+        tx.to = alice.address();
+        let invalid = BlockTx::new(tx.sign_by(&alice).unwrap(), 15, 21);
+        assert!(pool.upsert(invalid).is_err());
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn upsert_batch_admits_valid_txs() {
+        let alice = Signer::random();
+        let pool = MemPool::new();
+
+        let mut txs = (0..6)
+            .map(|nonce| block_tx(&alice, nonce, 10))
+            .collect::<Vec<_>>();
+        // signed by alice but sent to alice
+        txs[1] = BlockTx::new(
+            Transaction::new(1, alice.address(), 100, 10)
+                .sign_by(&alice)
+                .unwrap(),
+            15,
+            21,
+        );
+        // replaces nonce 0 without bumping the tip
+        txs.push(block_tx(&alice, 0, 10));
+
+        let err = pool.upsert_batch(txs).unwrap_err();
+        assert_eq!(err.failed, vec![1, 6]);
+        assert_eq!(pool.len(), 5);
+    }
+
+    #[test]
+    fn hold_time_locked_txs() {
+        let alice = Signer::random();
+        let pool = MemPool::new();
+        pool.advance(BlockInfo {
+            height: 10,
+            timestamp: 0,
+        });
+
+        let locked = |nonce, valid_after, valid_until| {
+            let tx = Transaction {
+                nonce,
+                valid_after,
+                valid_until,
+                ..Transaction::new(nonce, Signer::random().address(), 100, 10)
+            };
+            BlockTx::new(tx.sign_by(&alice).unwrap(), 15, 21)
+        };
+        pool.upsert(block_tx(&alice, 0, 10)).unwrap();
+        // vesting payment, held until height 12
+        pool.upsert(locked(1, Some(TimeLock::Height(12)), None))
+            .unwrap();
+        // scheduled payment, valid until height 11
+        pool.upsert(locked(2, None, Some(TimeLock::Height(11))))
+            .unwrap();
+        // already expired
+        assert!(
+            pool.upsert(locked(3, None, Some(TimeLock::Height(9))))
+                .is_err()
+        );
+
+        assert_eq!(pool.len(), 3);
+        // nonce 2 is valid but cannot be included before the held nonce 1
+        let nonces = pool
+            .ready()
+            .iter()
+            .map(|tx| tx.tx().nonce)
+            .collect::<Vec<_>>();
+        assert_eq!(nonces, [0]);
+
+        pool.advance(BlockInfo {
+            height: 12,
+            timestamp: 0,
+        });
+        let nonces = pool
+            .ready()
+            .iter()
+            .map(|tx| tx.tx().nonce)
+            .collect::<Vec<_>>();
+        assert_eq!(pool.len(), 2, "the scheduled payment expired");
+        assert_eq!(nonces, [0, 1]);
+    }
+
+    #[test]
+    fn ready_in_nonce_order() {
+        let alice = Signer::random();
+        let bob = Signer::random();
+        let pool = MemPool::new();
+        for nonce in [2, 0, 1, 4] {
+            pool.upsert(block_tx(&alice, nonce, 10)).unwrap();
+        }
+        pool.upsert(block_tx(&bob, 0, 10)).unwrap();
+
+        let ready = pool.ready();
+        let alice_nonces = ready
+            .iter()
+            .filter(|tx| tx.sender() == &alice.address())
+            .map(|tx| tx.tx().nonce)
+            .collect::<Vec<_>>();
+        // nonce 4 waits for the missing nonce 3
+        assert_eq!(alice_nonces, [0, 1, 2]);
+        assert_eq!(ready.len(), 4);
+    }
+
+    #[test]
+    fn reject_far_future_txs() {
+        let alice = Signer::random();
+        let pool = MemPool::new();
+        pool.advance(BlockInfo {
+            height: 10,
+            timestamp: 1_000,
+        });
+
+        let held = |nonce, valid_after| {
+            let mut tx = block_tx(&alice, nonce, 10).tx().clone();
+            tx.valid_after = Some(valid_after);
+            BlockTx::new(tx.sign_by(&alice).unwrap(), 15, 21)
+        };
+        pool.upsert(held(0, TimeLock::Height(10 + MAX_HOLD_HEIGHT)))
+            .unwrap();
+        assert!(
+            pool.upsert(held(1, TimeLock::Height(11 + MAX_HOLD_HEIGHT)))
+                .is_err()
+        );
+        pool.upsert(held(2, TimeLock::Timestamp(1_000 + MAX_HOLD_MILLIS)))
+            .unwrap();
+        assert!(
+            pool.upsert(held(3, TimeLock::Timestamp(1_001 + MAX_HOLD_MILLIS)))
+                .is_err()
+        );
+        assert_eq!(pool.len(), 2);
+    }
+}
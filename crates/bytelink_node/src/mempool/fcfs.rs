@@ -12,6 +12,12 @@ impl TimebasedSelector {
     }
 }
 
+impl Default for TimebasedSelector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[derive(Eq)]
 struct Item {
     timestamp: u64,
@@ -26,7 +32,7 @@ impl PartialEq<Self> for Item {
 
 impl PartialOrd<Self> for Item {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        other.timestamp.partial_cmp(&self.timestamp)
+        Some(self.cmp(other))
     }
 }
 
@@ -69,7 +75,7 @@ mod tests {
     use crate::mempool::Key;
     use crate::mempool::selector::{Selector, TxInfo};
     use bytelink_crypto::address::Address;
-    use rand::{Rng, rng};
+    use rand::rng;
 
     pub struct MockTxInfo {
         pub timestamp: u64,
@@ -111,7 +117,7 @@ mod tests {
 
     #[test]
     fn select_respects_max_block_size() {
-        let txs = (1..=5).map(|i| MockTxInfo::new(i)).collect::<Vec<_>>();
+        let txs = (1..=5).map(MockTxInfo::new).collect::<Vec<_>>();
         let selector = TimebasedSelector::new();
         let selected = selector.select(txs.into_iter(), 3);
         assert_eq!(selected.len(), 3);
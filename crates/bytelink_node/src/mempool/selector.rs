@@ -19,7 +19,7 @@ pub trait TxInfo {
 
 impl TxInfo for RefMulti<'_, Key, BlockTx> {
     fn timestamp(&self) -> u64 {
-        todo!()
+        self.value().timestamp
     }
     fn key(&self) -> Key {
         self.key().clone()
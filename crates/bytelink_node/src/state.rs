@@ -1,11 +1,30 @@
 use crate::database::Database;
+use crate::database::account::Account;
+use crate::database::history::History;
+use crate::database::htlc::Htlc;
+use crate::database::timelock::{BlockInfo, Validity};
+use crate::database::token::Token;
+use crate::database::transaction::{BlockTx, SignedTx, TxKind};
 use crate::genesis::Genesis;
+use crate::vm::{self, Call, MAX_CODE_SIZE, contract_address};
 use anyhow::Result;
 use bytelink_crypto::address::Address;
+use std::collections::HashMap;
+
+/// The gas used by every transaction.
+pub const TX_GAS: u64 = 21;
+/// The gas used by every byte of the code of a created contract.
+pub const CODE_BYTE_GAS: u64 = 2;
+/// The additional gas used by every token transaction.
+pub const TOKEN_GAS: u64 = 20;
+/// The additional gas used by every HTLC transaction.
+pub const HTLC_GAS: u64 = 20;
 
 pub struct Config {
     beneficiary: Address,
     genesis: Genesis,
+    /// index the transactions of every address as they are applied, see [`History`]
+    history: bool,
 }
 
 /// The state of the current blockchain node.
@@ -13,6 +32,107 @@ pub struct State {
     beneficiary: Address,
     genesis: Genesis,
     db: Database,
+    history: Option<History>,
+}
+
+/// The outcome of a transaction applied by [`State::apply`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Receipt {
+    pub gas_used: u64,
+    /// why the transaction reverted, if it did. A reverted transaction only
+    /// increments the nonce of the sender and charges the fees.
+    pub revert: Option<String>,
+}
+
+/// The changes of the transaction being applied, written to the database once it is done.
+#[derive(Clone, Default)]
+struct Changes {
+    accounts: HashMap<Address, Account>,
+    code: Option<(Address, Vec<u8>)>,
+    storage: HashMap<(Address, u64), u64>,
+    token: Option<(Address, Token)>,
+    token_balances: HashMap<(Address, Address), u64>,
+    allowances: HashMap<(Address, Address, Address), u64>,
+    locked: Option<(Address, Htlc)>,
+    settled: Option<Address>,
+}
+
+impl Changes {
+    /// The account as modified by the transaction, an empty one if it does not exist.
+    fn account(&mut self, db: &Database, address: &Address) -> &mut Account {
+        self.accounts.entry(address.clone()).or_insert_with(|| {
+            db.query(address)
+                .map(|account| account.clone())
+                .unwrap_or_else(|| Account::new(address.clone(), 0))
+        })
+    }
+
+    fn token_balance(&mut self, db: &Database, token: &Address, holder: &Address) -> &mut u64 {
+        self.token_balances
+            .entry((token.clone(), holder.clone()))
+            .or_insert_with(|| db.token_balance(token, holder))
+    }
+
+    fn allowance(
+        &mut self,
+        db: &Database,
+        token: &Address,
+        owner: &Address,
+        spender: &Address,
+    ) -> &mut u64 {
+        self.allowances
+            .entry((token.clone(), owner.clone(), spender.clone()))
+            .or_insert_with(|| db.allowance(token, owner, spender))
+    }
+
+    /// Move `amount` of the token from one holder to another.
+    fn move_tokens(
+        &mut self,
+        db: &Database,
+        token: &Address,
+        from: &Address,
+        to: &Address,
+        amount: u64,
+    ) -> Result<()> {
+        let balance = self.token_balance(db, token, from);
+        *balance = balance.checked_sub(amount).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Insufficient token balance: {} holds {balance}, needs {amount}",
+                from.checksummed()
+            )
+        })?;
+        // cannot overflow, the supply is fixed
+        *self.token_balance(db, token, to) += amount;
+        Ok(())
+    }
+
+    fn write(self, db: &Database) -> Result<()> {
+        if let Some((address, code)) = self.code {
+            db.set_code(address, code)?;
+        }
+        for ((address, key), value) in self.storage {
+            db.set_storage(address, key, value);
+        }
+        if let Some((id, token)) = self.token {
+            db.create_token(id, token)?;
+        }
+        for ((token, holder), balance) in self.token_balances {
+            db.set_token_balance(token, holder, balance);
+        }
+        for ((token, owner, spender), amount) in self.allowances {
+            db.set_allowance(token, owner, spender, amount);
+        }
+        if let Some((id, htlc)) = self.locked {
+            db.lock_htlc(id, htlc)?;
+        }
+        if let Some(id) = self.settled {
+            db.settle_htlc(&id)?;
+        }
+        for account in self.accounts.into_values() {
+            db.upsert(account);
+        }
+        Ok(())
+    }
 }
 
 impl State {
@@ -22,6 +142,769 @@ impl State {
             beneficiary: config.beneficiary,
             genesis: config.genesis,
             db,
+            history: config.history.then(History::new),
+        })
+    }
+
+    pub fn beneficiary(&self) -> &Address {
+        &self.beneficiary
+    }
+
+    pub fn genesis(&self) -> &Genesis {
+        &self.genesis
+    }
+
+    pub fn db(&self) -> &Database {
+        &self.db
+    }
+
+    /// The history of every address, `None` unless enabled in the [`Config`].
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+
+    /// Apply a transaction included in the block: the sender pays the value to the recipient,
+    /// and the tip and the gas used to the beneficiary. Sending value to a contract runs its code
+    /// with the data as input, metered by the `gas_units` of the transaction.
+    ///
+    /// Nothing is changed if the transaction is invalid in this block, i.e. if it is not properly
+    /// signed, not bound to this chain, time-locked, has the wrong nonce or the sender cannot pay
+    /// for all of its gas.
+    /// If it fails while running, it is reverted and all of its gas is charged.
+    /// Applied transactions, reverted or not, are recorded in the [`history`][State::history].
+    pub fn apply(&self, tx: &BlockTx, block: &BlockInfo) -> Result<Receipt> {
+        tx.signed_tx().verify()?;
+        self.apply_verified(tx, block)
+    }
+
+    /// [`apply`][State::apply] the transactions of a block in order, once their signatures
+    /// are all checked at once with [`SignedTx::verify_batch`].
+    ///
+    /// Nothing is changed if any signature is invalid. A transaction rejected while applying
+    /// stops the import, the ones before it stay applied.
+    pub fn apply_block(&self, txs: &[BlockTx], block: &BlockInfo) -> Result<Vec<Receipt>> {
+        SignedTx::verify_batch(txs.iter().map(BlockTx::signed_tx))?;
+        txs.iter()
+            .map(|tx| self.apply_verified(tx, block))
+            .collect()
+    }
+
+    /// [`apply`][State::apply] a transaction whose signature is already verified.
+    fn apply_verified(&self, tx: &BlockTx, block: &BlockInfo) -> Result<Receipt> {
+        // a block applied out of order is rejected before changing anything
+        if let Some(history) = &self.history {
+            history.check(block.height)?;
+        }
+        let transaction = tx.tx();
+        if transaction.chain_id != Some(self.genesis.chain_id) {
+            anyhow::bail!(
+                "Invalid transaction: not bound to chain {}",
+                self.genesis.chain_id
+            );
+        }
+        match transaction.validity(block) {
+            Validity::Valid => {}
+            Validity::Pending => anyhow::bail!("Invalid transaction: not valid yet"),
+            Validity::Expired => anyhow::bail!("Invalid transaction: expired"),
+        }
+
+        let intrinsic_gas = Self::intrinsic_gas(tx);
+        if tx.gas_units < intrinsic_gas {
+            anyhow::bail!(
+                "Invalid transaction: gas limit {} below intrinsic gas {intrinsic_gas}",
+                tx.gas_units
+            );
+        }
+        let max_cost = tx
+            .gas_units
+            .checked_mul(tx.gas_price)
+            .and_then(|fee| fee.checked_add(transaction.tip))
+            .and_then(|cost| cost.checked_add(transaction.value))
+            .ok_or_else(|| anyhow::anyhow!("Invalid transaction: cost overflow"))?;
+
+        let mut changes = Changes::default();
+        let sender = changes.account(&self.db, tx.sender());
+        if sender.nonce() != transaction.nonce {
+            anyhow::bail!(
+                "Invalid transaction nonce: expected {}, got {}",
+                sender.nonce(),
+                transaction.nonce
+            );
+        }
+        if sender.balance() < max_cost {
+            anyhow::bail!(
+                "Insufficient balance: {} holds {}, needs {max_cost}",
+                tx.sender().checksummed(),
+                sender.balance()
+            );
+        }
+        sender.increment_nonce();
+
+        // the effects of the transaction are dropped if it fails
+        let mut effects = changes.clone();
+        let (gas_used, revert) = match self.execute(&mut effects, tx, block, intrinsic_gas) {
+            Ok(gas_used) => {
+                changes = effects;
+                (gas_used, None)
+            }
+            Err(err) => (tx.gas_units, Some(err.to_string())),
+        };
+
+        let fee = gas_used * tx.gas_price + transaction.tip;
+        changes.account(&self.db, tx.sender()).debit(fee)?;
+        changes.account(&self.db, &self.beneficiary).credit(fee)?;
+        changes.write(&self.db)?;
+
+        let receipt = Receipt { gas_used, revert };
+        if let Some(history) = &self.history {
+            history.record(block.height, tx, &receipt)?;
+        }
+        Ok(receipt)
+    }
+
+    /// The gas used by the transaction before running any code.
+    fn intrinsic_gas(tx: &BlockTx) -> u64 {
+        match tx.tx().kind {
+            TxKind::Transfer => TX_GAS,
+            TxKind::Create => TX_GAS + CODE_BYTE_GAS * tx.tx().data.len() as u64,
+            TxKind::CreateToken { .. }
+            | TxKind::TokenTransfer { .. }
+            | TxKind::TokenApprove { .. }
+            | TxKind::TokenTransferFrom { .. } => TX_GAS + TOKEN_GAS,
+            TxKind::HtlcLock { .. } | TxKind::HtlcClaim | TxKind::HtlcRefund => TX_GAS + HTLC_GAS,
+        }
+    }
+
+    /// Record the effects of the transaction into `changes`, returning the gas used.
+    fn execute(
+        &self,
+        changes: &mut Changes,
+        tx: &BlockTx,
+        block: &BlockInfo,
+        intrinsic_gas: u64,
+    ) -> Result<u64> {
+        let transaction = tx.tx();
+        let sender = tx.sender();
+        changes.account(&self.db, sender).debit(transaction.value)?;
+        match transaction.kind {
+            // the value of a lock is held in escrow
+            TxKind::HtlcLock { .. } => {}
+            TxKind::HtlcClaim | TxKind::HtlcRefund if transaction.value != 0 => {
+                anyhow::bail!("Invalid HTLC settlement: cannot send value to an HTLC")
+            }
+            // the escrowed funds are released below, the HTLC id is not an account
+            TxKind::HtlcClaim | TxKind::HtlcRefund => {}
+            // the token id is not an account, a value sent to it could never be spent
+            TxKind::CreateToken { .. } if transaction.value != 0 => {
+                anyhow::bail!("Invalid token creation: cannot send value to a token")
+            }
+            _ => changes
+                .account(&self.db, &transaction.to)
+                .credit(transaction.value)?,
+        }
+
+        match &transaction.kind {
+            TxKind::Create => {
+                let address = Self::created_address(tx)?;
+                if transaction.data.is_empty() || transaction.data.len() > MAX_CODE_SIZE {
+                    anyhow::bail!(
+                        "Invalid contract code: expected 1 to {MAX_CODE_SIZE} bytes, got {}",
+                        transaction.data.len()
+                    );
+                }
+                if self.db.code(&address).is_some() {
+                    anyhow::bail!("Contract already exists: {}", address.checksummed());
+                }
+                changes.code = Some((address, transaction.data.clone()));
+                Ok(intrinsic_gas)
+            }
+            TxKind::Transfer => {
+                let Some(code) = self.db.code(&transaction.to) else {
+                    return Ok(intrinsic_gas);
+                };
+                let call = Call {
+                    code: &code,
+                    input: &transaction.data,
+                    value: transaction.value,
+                    gas_limit: tx.gas_units - intrinsic_gas,
+                };
+                let execution = vm::execute(&call, |key| self.db.storage(&transaction.to, key))?;
+                for (key, value) in execution.writes {
+                    changes.storage.insert((transaction.to.clone(), key), value);
+                }
+                Ok(intrinsic_gas + execution.gas_used)
+            }
+            TxKind::CreateToken { supply } => {
+                let id = Self::created_address(tx)?;
+                if self.db.token(&id).is_some() {
+                    anyhow::bail!("Token already exists: {}", id.checksummed());
+                }
+                *changes.token_balance(&self.db, &id, sender) = *supply;
+                changes.token = Some((id, Token::new(sender.clone(), *supply)));
+                Ok(intrinsic_gas)
+            }
+            TxKind::TokenTransfer { token, amount } => {
+                self.ensure_token(token)?;
+                changes.move_tokens(&self.db, token, sender, &transaction.to, *amount)?;
+                Ok(intrinsic_gas)
+            }
+            TxKind::TokenApprove { token, amount } => {
+                self.ensure_token(token)?;
+                *changes.allowance(&self.db, token, sender, &transaction.to) = *amount;
+                Ok(intrinsic_gas)
+            }
+            TxKind::TokenTransferFrom {
+                token,
+                from,
+                amount,
+            } => {
+                self.ensure_token(token)?;
+                let allowance = changes.allowance(&self.db, token, from, sender);
+                *allowance = allowance.checked_sub(*amount).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Insufficient allowance: {} may spend {allowance}, needs {amount}",
+                        sender.checksummed()
+                    )
+                })?;
+                changes.move_tokens(&self.db, token, from, &transaction.to, *amount)?;
+                Ok(intrinsic_gas)
+            }
+            TxKind::HtlcLock { hashlock, timeout } => {
+                let id = contract_address(sender, transaction.nonce);
+                let htlc = Htlc {
+                    sender: sender.clone(),
+                    recipient: transaction.to.clone(),
+                    amount: transaction.value,
+                    hashlock: *hashlock,
+                    timeout: *timeout,
+                };
+                if htlc.amount == 0 {
+                    anyhow::bail!("Invalid HTLC: nothing to lock");
+                }
+                if htlc.is_expired(block) {
+                    anyhow::bail!("Invalid HTLC: timeout already passed");
+                }
+                changes.locked = Some((id, htlc));
+                Ok(intrinsic_gas)
+            }
+            TxKind::HtlcClaim => {
+                let htlc = self.pending_htlc(&transaction.to)?;
+                if htlc.is_expired(block) {
+                    anyhow::bail!("HTLC expired: {}", transaction.to.checksummed());
+                }
+                htlc.unlock(&transaction.data)?;
+                changes
+                    .account(&self.db, &htlc.recipient)
+                    .credit(htlc.amount)?;
+                changes.settled = Some(transaction.to.clone());
+                Ok(intrinsic_gas)
+            }
+            TxKind::HtlcRefund => {
+                let htlc = self.pending_htlc(&transaction.to)?;
+                if !htlc.is_expired(block) {
+                    anyhow::bail!("HTLC not expired yet: {}", transaction.to.checksummed());
+                }
+                changes
+                    .account(&self.db, &htlc.sender)
+                    .credit(htlc.amount)?;
+                changes.settled = Some(transaction.to.clone());
+                Ok(intrinsic_gas)
+            }
+        }
+    }
+
+    /// The address of the contract or token created by the transaction, which must be its recipient.
+    fn created_address(tx: &BlockTx) -> Result<Address> {
+        let address = contract_address(tx.sender(), tx.tx().nonce);
+        if tx.tx().to != address {
+            anyhow::bail!(
+                "Invalid created address: expected {}, got {}",
+                address.checksummed(),
+                tx.tx().to.checksummed()
+            );
+        }
+        Ok(address)
+    }
+
+    fn pending_htlc(&self, id: &Address) -> Result<Htlc> {
+        self.db
+            .htlc(id)
+            .ok_or_else(|| anyhow::anyhow!("Unknown HTLC: {}", id.checksummed()))
+    }
+
+    fn ensure_token(&self, token: &Address) -> Result<()> {
+        if self.db.token(token).is_none() {
+            anyhow::bail!("Unknown token: {}", token.checksummed());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::account::Account;
+    use crate::database::timelock::{BlockInfo, TimeLock};
+    use crate::database::transaction::{BlockTx, Transaction, TxKind};
+    use crate::genesis::Genesis;
+    use crate::state::{Config, HTLC_GAS, State, TOKEN_GAS, TX_GAS};
+    use crate::vm::{contract_address, opcode};
+    use alloy_primitives::keccak256;
+    use bytelink_crypto::address::Address;
+    use bytelink_crypto::batch::BatchError;
+    use bytelink_crypto::signer::Signer;
+    use rand::rng;
+
+    fn state(funded: &Signer) -> State {
+        let state = State::new(Config {
+            beneficiary: Address::random(rng()),
+            genesis: Genesis::load("../../assets/genesis.json").unwrap(),
+            history: false,
+        })
+        .unwrap();
+        state.db().upsert(Account::new(funded.address(), 1_000));
+        state
+    }
+
+    fn transfer(nonce: u64, to: &Address) -> Transaction {
+        Transaction {
+            chain_id: Some(1),
+            ..Transaction::new(nonce, to.clone(), 100, 5)
+        }
+    }
+
+    fn block_tx(signer: &Signer, tx: Transaction, gas_units: u64) -> BlockTx {
+        BlockTx::new(tx.sign_by(signer).unwrap(), 1, gas_units)
+    }
+
+    fn balance(state: &State, address: &Address) -> u64 {
+        state.db().query(address).map_or(0, |a| a.balance())
+    }
+
+    /// Lock 100 for bob until height 10, returning the HTLC id.
+    fn lock(state: &State, alice: &Signer, bob: &Address, nonce: u64) -> Address {
+        let mut tx = transfer(nonce, bob);
+        tx.kind = TxKind::HtlcLock {
+            hashlock: keccak256(b"secret"),
+            timeout: TimeLock::Height(10),
+        };
+        let receipt = state
+            .apply(&block_tx(alice, tx, TX_GAS + HTLC_GAS), &at(1))
+            .unwrap();
+        assert_eq!(receipt.revert, None);
+        contract_address(&alice.address(), nonce)
+    }
+
+    /// Claim or refund the HTLC from `signer`.
+    fn settle(
+        state: &State,
+        signer: &Signer,
+        id: &Address,
+        kind: TxKind,
+        preimage: &[u8],
+        block: BlockInfo,
+    ) -> Option<String> {
+        let nonce = state.db().query(signer.address()).map_or(0, |a| a.nonce());
+        let mut tx = transfer(nonce, id);
+        tx.value = 0;
+        tx.kind = kind;
+        tx.data = preimage.to_vec();
+        let tx = block_tx(signer, tx, TX_GAS + HTLC_GAS);
+        state.apply(&tx, &block).unwrap().revert
+    }
+
+    fn at(height: u64) -> BlockInfo {
+        BlockInfo {
+            height,
+            timestamp: 0,
+        }
+    }
+
+    fn push(word: u64) -> Vec<u8> {
+        [&[opcode::PUSH][..], &word.to_be_bytes()].concat()
+    }
+
+    /// Add the input word to the counter at key 0, reverting if the input is zero.
+    fn counter() -> Vec<u8> {
+        [
+            push(0),
+            vec![opcode::DUP, opcode::SLOAD],
+            push(0),
+            vec![opcode::CALLDATALOAD, opcode::DUP, opcode::ISZERO],
+            push(37),
+            vec![opcode::SWAP, opcode::JUMPI, opcode::ADD, opcode::SSTORE],
+            vec![opcode::STOP, opcode::REVERT],
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn apply_transfer() {
+        let alice = Signer::random();
+        let bob = Address::random(rng());
+        let state = state(&alice);
+        let block = BlockInfo::default();
+
+        let receipt = state
+            .apply(&block_tx(&alice, transfer(0, &bob), TX_GAS), &block)
+            .unwrap();
+        assert_eq!(receipt.gas_used, TX_GAS);
+        assert_eq!(balance(&state, &alice.address()), 1_000 - 100 - 5 - TX_GAS);
+        assert_eq!(balance(&state, &bob), 100);
+        assert_eq!(balance(&state, state.beneficiary()), 5 + TX_GAS);
+        assert_eq!(state.db().query(alice.address()).unwrap().nonce(), 1);
+
+        // replayed
+        let replayed = block_tx(&alice, transfer(0, &bob), TX_GAS);
+        assert!(state.apply(&replayed, &block).is_err());
+        // not enough gas
+        let underpaid = block_tx(&alice, transfer(1, &bob), TX_GAS - 1);
+        assert!(state.apply(&underpaid, &block).is_err());
+        // meant for another chain, or for any chain
+        for chain_id in [Some(2), None] {
+            let mut tx = transfer(1, &bob);
+            tx.chain_id = chain_id;
+            assert!(state.apply(&block_tx(&alice, tx, TX_GAS), &block).is_err());
+        }
+        // unfunded
+        let carol = Signer::random();
+        let unfunded = block_tx(&carol, transfer(0, &bob), TX_GAS);
+        assert!(state.apply(&unfunded, &block).is_err());
+        assert_eq!(balance(&state, &bob), 100);
+    }
+
+    #[test]
+    fn apply_block() {
+        let alice = Signer::random();
+        let bob = Address::random(rng());
+        let state = state(&alice);
+        let block = BlockInfo::default();
+
+        // signed by alice but sent to alice
+        let txs = [
+            block_tx(&alice, transfer(0, &bob), TX_GAS),
+            block_tx(&alice, transfer(1, &alice.address()), TX_GAS),
+        ];
+        let err = state.apply_block(&txs, &block).unwrap_err();
+        let err = err.downcast::<BatchError>().unwrap();
+        assert_eq!(err.failed, vec![1]);
+        assert_eq!(balance(&state, &bob), 0, "nothing is applied");
+
+        let txs = [
+            block_tx(&alice, transfer(0, &bob), TX_GAS),
+            block_tx(&alice, transfer(1, &bob), TX_GAS),
+        ];
+        let receipts = state.apply_block(&txs, &block).unwrap();
+        assert!(receipts.iter().all(|receipt| receipt.revert.is_none()));
+        assert_eq!(balance(&state, &bob), 200);
+        assert_eq!(state.db().query(alice.address()).unwrap().nonce(), 2);
+    }
+
+    #[test]
+    fn apply_time_locked_transfer() {
+        let alice = Signer::random();
+        let bob = Address::random(rng());
+        let state = state(&alice);
+
+        let mut tx = transfer(0, &bob);
+        tx.valid_after = Some(TimeLock::Timestamp(1_000));
+        tx.valid_until = Some(TimeLock::Height(5));
+        let tx = block_tx(&alice, tx, TX_GAS);
+
+        let at = |height, timestamp| BlockInfo { height, timestamp };
+        assert!(state.apply(&tx, &at(1, 999)).is_err(), "not valid yet");
+        assert!(state.apply(&tx, &at(6, 1_000)).is_err(), "expired");
+        assert_eq!(balance(&state, &bob), 0);
+
+        state.apply(&tx, &at(5, 1_000)).unwrap();
+        assert_eq!(balance(&state, &bob), 100);
+    }
+
+    #[test]
+    fn deploy_and_call_contract() {
+        let alice = Signer::random();
+        let state = state(&alice);
+        let block = BlockInfo::default();
+        let contract = contract_address(&alice.address(), 0);
+
+        let mut create = transfer(0, &contract);
+        create.kind = TxKind::Create;
+        create.value = 0;
+        create.data = counter();
+        let receipt = state
+            .apply(&block_tx(&alice, create.clone(), 200), &block)
+            .unwrap();
+        assert_eq!(receipt.revert, None);
+        assert_eq!(state.db().code(&contract), Some(counter()));
+
+        let call = |nonce, word: u64, gas_units| {
+            let mut tx = transfer(nonce, &contract);
+            tx.data = word.to_be_bytes().to_vec();
+            state
+                .apply(&block_tx(&alice, tx, gas_units), &block)
+                .unwrap()
+        };
+        assert_eq!(call(1, 40, 200).revert, None);
+        assert_eq!(call(2, 2, 200).revert, None);
+        assert_eq!(state.db().storage(&contract, 0), 42);
+        assert_eq!(balance(&state, &contract), 200);
+
+        // a reverted call keeps the storage and the value, but charges all of its gas
+        let before = balance(&state, &alice.address());
+        let reverted = call(3, 0, 200);
+        assert!(reverted.revert.is_some());
+        assert_eq!(reverted.gas_used, 200);
+        assert_eq!(balance(&state, &alice.address()), before - 200 - 5);
+        assert_eq!(balance(&state, &contract), 200);
+        assert_eq!(state.db().query(alice.address()).unwrap().nonce(), 4);
+
+        // out of gas
+        assert!(call(4, 1, TX_GAS + 10).revert.is_some());
+        assert_eq!(state.db().storage(&contract, 0), 42);
+
+        // a contract is created once, at the address of the sender and nonce
+        create.nonce = 5;
+        let receipt = state.apply(&block_tx(&alice, create, 200), &block).unwrap();
+        assert!(receipt.revert.is_some());
+    }
+
+    #[test]
+    fn token_lifecycle() {
+        let alice = Signer::random();
+        let bob = Signer::random();
+        let carol = Address::random(rng());
+        let state = state(&alice);
+        state.db().upsert(Account::new(bob.address(), 1_000));
+        let block = BlockInfo::default();
+        let token = contract_address(&alice.address(), 0);
+        let gas = TX_GAS + TOKEN_GAS;
+
+        let send = |signer: &Signer, nonce, to: &Address, kind| {
+            let mut tx = transfer(nonce, to);
+            tx.value = 0;
+            tx.kind = kind;
+            state.apply(&block_tx(signer, tx, gas), &block).unwrap()
+        };
+
+        // a creation cannot carry value
+        let dave = Signer::random();
+        state.db().upsert(Account::new(dave.address(), 1_000));
+        let mut create = transfer(0, &contract_address(&dave.address(), 0));
+        create.kind = TxKind::CreateToken { supply: 1_000 };
+        let receipt = state.apply(&block_tx(&dave, create, gas), &block).unwrap();
+        assert!(receipt.revert.is_some());
+        assert!(
+            state
+                .db()
+                .token(&contract_address(&dave.address(), 0))
+                .is_none()
+        );
+        assert_eq!(balance(&state, &dave.address()), 1_000 - gas - 5);
+
+        let receipt = send(&alice, 0, &token, TxKind::CreateToken { supply: 1_000 });
+        assert_eq!(receipt.revert, None);
+        assert_eq!(receipt.gas_used, gas);
+        assert_eq!(state.db().token(&token).unwrap().supply(), 1_000);
+        assert_eq!(state.db().token_balance(&token, &alice.address()), 1_000);
+
+        let transfer_to_bob = TxKind::TokenTransfer {
+            token: token.clone(),
+            amount: 300,
+        };
+        assert_eq!(
+            send(&alice, 1, &bob.address(), transfer_to_bob).revert,
+            None
+        );
+        assert_eq!(state.db().token_balance(&token, &bob.address()), 300);
+
+        let approve_bob = TxKind::TokenApprove {
+            token: token.clone(),
+            amount: 200,
+        };
+        assert_eq!(send(&alice, 2, &bob.address(), approve_bob).revert, None);
+        let spend = |amount| TxKind::TokenTransferFrom {
+            token: token.clone(),
+            from: alice.address(),
+            amount,
+        };
+        assert_eq!(send(&bob, 0, &carol, spend(150)).revert, None);
+        assert_eq!(state.db().token_balance(&token, &alice.address()), 550);
+        assert_eq!(state.db().token_balance(&token, &carol), 150);
+        assert_eq!(
+            state
+                .db()
+                .allowance(&token, &alice.address(), &bob.address()),
+            50
+        );
+
+        // beyond the allowance, the nonce is used and the gas charged
+        let before = balance(&state, &bob.address());
+        assert!(send(&bob, 1, &carol, spend(60)).revert.is_some());
+        assert_eq!(state.db().token_balance(&token, &carol), 150);
+        assert_eq!(balance(&state, &bob.address()), before - gas - 5);
+        assert_eq!(state.db().query(bob.address()).unwrap().nonce(), 2);
+
+        // beyond the balance
+        let overdraft = TxKind::TokenTransfer {
+            token: token.clone(),
+            amount: 301,
+        };
+        assert!(send(&bob, 2, &carol, overdraft).revert.is_some());
+        // unknown token
+        let unknown = TxKind::TokenApprove {
+            token: carol.clone(),
+            amount: 1,
+        };
+        assert!(send(&bob, 3, &alice.address(), unknown).revert.is_some());
+        // a token id is bound to the transaction creating it
+        assert!(
+            send(&alice, 3, &token, TxKind::CreateToken { supply: 1 })
+                .revert
+                .is_some()
+        );
+
+        assert_eq!(
+            state.db().token_balances(&bob.address()),
+            vec![(token.clone(), 300)]
+        );
+        let total: u64 = [alice.address(), bob.address(), carol.clone()]
+            .iter()
+            .map(|holder| state.db().token_balance(&token, holder))
+            .sum();
+        assert_eq!(total, 1_000);
+    }
+
+    #[test]
+    fn claim_htlc() {
+        let alice = Signer::random();
+        let bob = Signer::random();
+        let state = state(&alice);
+        state.db().upsert(Account::new(bob.address(), 1_000));
+        let id = lock(&state, &alice, &bob.address(), 0);
+
+        // held in escrow
+        let gas = TX_GAS + HTLC_GAS;
+        assert_eq!(balance(&state, &alice.address()), 1_000 - 100 - 5 - gas);
+        assert_eq!(balance(&state, &id), 0);
+        assert_eq!(state.db().htlc(&id).unwrap().amount, 100);
+
+        let claim = |preimage: &[u8], height| {
+            settle(&state, &bob, &id, TxKind::HtlcClaim, preimage, at(height))
+        };
+        assert!(claim(b"guess", 5).is_some(), "wrong preimage");
+        assert!(settle(&state, &alice, &id, TxKind::HtlcRefund, b"", at(10)).is_some());
+
+        let before = balance(&state, &bob.address());
+        assert_eq!(claim(b"secret", 10), None);
+        assert_eq!(balance(&state, &bob.address()), before + 100 - 5 - gas);
+        assert!(state.db().htlc(&id).is_none());
+        assert!(state.db().query(&id).is_none(), "no account at the HTLC id");
+
+        // double spend
+        assert!(claim(b"secret", 10).is_some());
+        assert!(settle(&state, &alice, &id, TxKind::HtlcRefund, b"", at(11)).is_some());
+        assert_eq!(
+            balance(&state, &bob.address()),
+            before + 100 - 2 * (5 + gas)
+        );
+    }
+
+    #[test]
+    fn refund_htlc() {
+        let alice = Signer::random();
+        let bob = Signer::random();
+        let state = state(&alice);
+        state.db().upsert(Account::new(bob.address(), 1_000));
+        let id = lock(&state, &alice, &bob.address(), 0);
+
+        // the secret is revealed too late
+        assert!(settle(&state, &bob, &id, TxKind::HtlcClaim, b"secret", at(11)).is_some());
+
+        let before = balance(&state, &alice.address());
+        let gas = TX_GAS + HTLC_GAS;
+        assert_eq!(
+            settle(&state, &alice, &id, TxKind::HtlcRefund, b"", at(11)),
+            None
+        );
+        assert_eq!(balance(&state, &alice.address()), before + 100 - 5 - gas);
+
+        // double spend
+        assert!(settle(&state, &alice, &id, TxKind::HtlcRefund, b"", at(11)).is_some());
+        assert!(settle(&state, &bob, &id, TxKind::HtlcClaim, b"secret", at(11)).is_some());
+        assert_eq!(balance(&state, &bob.address()), 1_000 - 2 * (5 + gas));
+    }
+
+    #[test]
+    fn reject_invalid_htlc() {
+        let alice = Signer::random();
+        let bob = Address::random(rng());
+        let state = state(&alice);
+
+        let mut tx = transfer(0, &bob);
+        tx.kind = TxKind::HtlcLock {
+            hashlock: keccak256(b"secret"),
+            timeout: TimeLock::Height(10),
+        };
+        let tx = block_tx(&alice, tx, TX_GAS + HTLC_GAS);
+        let receipt = state.apply(&tx, &at(11)).unwrap();
+        assert!(receipt.revert.is_some(), "timeout already passed");
+        assert!(
+            state
+                .db()
+                .htlc(&contract_address(&alice.address(), 0))
+                .is_none()
+        );
+        assert_eq!(
+            balance(&state, &alice.address()),
+            1_000 - 5 - TX_GAS - HTLC_GAS
+        );
+
+        // settlements cannot carry value
+        let id = lock(&state, &alice, &bob, 1);
+        let mut claim = transfer(2, &id);
+        claim.kind = TxKind::HtlcClaim;
+        claim.data = b"secret".to_vec();
+        let claim = block_tx(&alice, claim, TX_GAS + HTLC_GAS);
+        assert!(state.apply(&claim, &at(1)).unwrap().revert.is_some());
+        assert!(state.db().htlc(&id).is_some());
+    }
+
+    #[test]
+    fn record_history() {
+        let alice = Signer::random();
+        let bob = Signer::random();
+        let state = State::new(Config {
+            beneficiary: Address::random(rng()),
+            genesis: Genesis::load("../../assets/genesis.json").unwrap(),
+            history: true,
         })
+        .unwrap();
+        state.db().upsert(Account::new(alice.address(), 1_000));
+        let id = lock(&state, &alice, &bob.address(), 0);
+
+        // claimed by a relayer, paid to bob
+        let relayer = Signer::random();
+        state.db().upsert(Account::new(relayer.address(), 1_000));
+        let claimed = settle(&state, &relayer, &id, TxKind::HtlcClaim, b"secret", at(2));
+        assert_eq!(claimed, None);
+
+        let history = state.history().unwrap();
+        let heights = |address: &Address| {
+            let page = history.page(address, 0, 10);
+            page.records.iter().map(|r| r.height).collect::<Vec<_>>()
+        };
+        assert_eq!(heights(&alice.address()), [1]);
+        assert_eq!(heights(&bob.address()), [1, 2]);
+        assert_eq!(heights(&relayer.address()), [2]);
+
+        // blocks must be applied in order
+        let late = block_tx(&alice, transfer(1, &bob.address()), TX_GAS);
+        assert!(state.apply(&late, &at(1)).is_err());
+        assert_eq!(balance(&state, &bob.address()), 100);
+        assert_eq!(state.db().query(alice.address()).unwrap().nonce(), 1);
+
+        // a rejected transaction is not recorded
+        let rejected = block_tx(&alice, transfer(5, &bob.address()), TX_GAS);
+        assert!(state.apply(&rejected, &at(3)).is_err());
+        assert_eq!(heights(&bob.address()), [1, 2]);
     }
 }
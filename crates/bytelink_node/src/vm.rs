@@ -0,0 +1,320 @@
+use alloy_primitives::keccak256;
+use anyhow::Result;
+use bytelink_crypto::address::Address;
+use std::collections::HashMap;
+
+/// The maximum size of the code of a contract.
+pub const MAX_CODE_SIZE: usize = 24 * 1024;
+/// The maximum number of words on the stack.
+pub const MAX_STACK_SIZE: usize = 1024;
+
+/// The instructions of the VM, one byte each. Only [`PUSH`][opcode::PUSH] has an immediate,
+/// the 8-byte big-endian word following it.
+///
+/// The VM works on 64-bit words, arithmetic wraps around and comparisons push `1` or `0`.
+pub mod opcode {
+    /// stop successfully
+    pub const STOP: u8 = 0x00;
+    /// `a b -> a + b`
+    pub const ADD: u8 = 0x01;
+    /// `a b -> a - b`
+    pub const SUB: u8 = 0x02;
+    /// `a b -> a * b`
+    pub const MUL: u8 = 0x03;
+    /// `a b -> a / b`, fails if `b` is zero
+    pub const DIV: u8 = 0x04;
+    /// `a b -> a % b`, fails if `b` is zero
+    pub const MOD: u8 = 0x05;
+    /// `a b -> a < b`
+    pub const LT: u8 = 0x10;
+    /// `a b -> a > b`
+    pub const GT: u8 = 0x11;
+    /// `a b -> a == b`
+    pub const EQ: u8 = 0x12;
+    /// `a -> a == 0`
+    pub const ISZERO: u8 = 0x13;
+    /// `a b -> a & b`
+    pub const AND: u8 = 0x14;
+    /// `a b -> a | b`
+    pub const OR: u8 = 0x15;
+    /// `a ->`
+    pub const POP: u8 = 0x20;
+    /// `a -> a a`
+    pub const DUP: u8 = 0x21;
+    /// `a b -> b a`
+    pub const SWAP: u8 = 0x22;
+    /// `-> word`, the immediate word
+    pub const PUSH: u8 = 0x30;
+    /// `dest ->`, continue at the `dest` byte of the code
+    pub const JUMP: u8 = 0x40;
+    /// `dest cond ->`, jump to `dest` if `cond` is not zero
+    pub const JUMPI: u8 = 0x41;
+    /// `key -> value`, read the storage of the contract
+    pub const SLOAD: u8 = 0x50;
+    /// `key value ->`, write the storage of the contract
+    pub const SSTORE: u8 = 0x51;
+    /// `-> value`, the value sent to the contract
+    pub const CALLVALUE: u8 = 0x60;
+    /// `offset -> word`, the input word at `offset`, zero padded
+    pub const CALLDATALOAD: u8 = 0x61;
+    /// `-> size`, the size of the input in bytes
+    pub const CALLDATASIZE: u8 = 0x62;
+    /// `a ->`, stop successfully with `a` as output
+    pub const RETURN: u8 = 0xf0;
+    /// stop and revert every change of the call
+    pub const REVERT: u8 = 0xfd;
+}
+
+/// The gas cost of an instruction.
+fn gas_cost(op: u8) -> u64 {
+    match op {
+        opcode::SLOAD => 20,
+        opcode::SSTORE => 50,
+        opcode::MUL | opcode::DIV | opcode::MOD => 3,
+        _ => 1,
+    }
+}
+
+/// The address of the contract created by the transaction of `sender` at `nonce`,
+/// the last 20 bytes of the Keccak-256 hash of the sender followed by the big-endian nonce.
+pub fn contract_address(sender: &Address, nonce: u64) -> Address {
+    let mut buf = [0u8; 28];
+    buf[..20].copy_from_slice(sender.as_bytes());
+    buf[20..].copy_from_slice(&nonce.to_be_bytes());
+    Address::from_slice(&keccak256(buf)[12..]).expect("hash is 32 bytes long")
+}
+
+/// A call to a contract.
+pub struct Call<'a> {
+    pub code: &'a [u8],
+    /// the data payload of the transaction
+    pub input: &'a [u8],
+    pub value: u64,
+    pub gas_limit: u64,
+}
+
+/// The result of a successful call.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Execution {
+    pub gas_used: u64,
+    pub output: Option<u64>,
+    /// the storage written by the call, to be applied to the contract
+    pub writes: HashMap<u64, u64>,
+}
+
+/// Run a contract call. `load` reads the storage of the contract as of before the call.
+///
+/// A failed call, including one running out of gas, uses all of its gas and none of its
+/// writes must be applied.
+pub fn execute<F>(call: &Call<'_>, load: F) -> Result<Execution>
+where
+    F: Fn(u64) -> u64,
+{
+    let mut execution = Execution::default();
+    let mut stack: Vec<u64> = Vec::new();
+    let mut pc = 0;
+
+    while let Some(&op) = call.code.get(pc) {
+        execution.gas_used += gas_cost(op);
+        if execution.gas_used > call.gas_limit {
+            anyhow::bail!("Out of gas: limit {}", call.gas_limit);
+        }
+        let at = pc;
+        pc += 1;
+
+        let pop = |stack: &mut Vec<u64>| {
+            stack
+                .pop()
+                .ok_or_else(|| anyhow::anyhow!("Stack underflow at {at}"))
+        };
+        let push = match op {
+            opcode::STOP => return Ok(execution),
+            opcode::ADD => Some(pop(&mut stack)?.wrapping_add(pop(&mut stack)?)),
+            opcode::SUB => {
+                let (b, a) = (pop(&mut stack)?, pop(&mut stack)?);
+                Some(a.wrapping_sub(b))
+            }
+            opcode::MUL => Some(pop(&mut stack)?.wrapping_mul(pop(&mut stack)?)),
+            opcode::DIV | opcode::MOD => {
+                let (b, a) = (pop(&mut stack)?, pop(&mut stack)?);
+                if b == 0 {
+                    anyhow::bail!("Division by zero at {at}");
+                }
+                Some(if op == opcode::DIV { a / b } else { a % b })
+            }
+            opcode::LT => {
+                let (b, a) = (pop(&mut stack)?, pop(&mut stack)?);
+                Some((a < b) as u64)
+            }
+            opcode::GT => {
+                let (b, a) = (pop(&mut stack)?, pop(&mut stack)?);
+                Some((a > b) as u64)
+            }
+            opcode::EQ => Some((pop(&mut stack)? == pop(&mut stack)?) as u64),
+            opcode::ISZERO => Some((pop(&mut stack)? == 0) as u64),
+            opcode::AND => Some(pop(&mut stack)? & pop(&mut stack)?),
+            opcode::OR => Some(pop(&mut stack)? | pop(&mut stack)?),
+            opcode::POP => {
+                pop(&mut stack)?;
+                None
+            }
+            opcode::DUP => {
+                let a = pop(&mut stack)?;
+                stack.push(a);
+                Some(a)
+            }
+            opcode::SWAP => {
+                let (b, a) = (pop(&mut stack)?, pop(&mut stack)?);
+                stack.push(b);
+                Some(a)
+            }
+            opcode::PUSH => {
+                let word = call
+                    .code
+                    .get(pc..pc + 8)
+                    .ok_or_else(|| anyhow::anyhow!("Truncated push at {at}"))?;
+                pc += 8;
+                Some(u64::from_be_bytes(word.try_into()?))
+            }
+            opcode::JUMP | opcode::JUMPI => {
+                let dest = if op == opcode::JUMP {
+                    pop(&mut stack)?
+                } else {
+                    let (cond, dest) = (pop(&mut stack)?, pop(&mut stack)?);
+                    if cond == 0 { pc as u64 } else { dest }
+                };
+                if dest >= call.code.len() as u64 {
+                    anyhow::bail!("Invalid jump destination: {dest}");
+                }
+                pc = dest as usize;
+                None
+            }
+            opcode::SLOAD => {
+                let key = pop(&mut stack)?;
+                Some(match execution.writes.get(&key) {
+                    Some(value) => *value,
+                    None => load(key),
+                })
+            }
+            opcode::SSTORE => {
+                let (value, key) = (pop(&mut stack)?, pop(&mut stack)?);
+                execution.writes.insert(key, value);
+                None
+            }
+            opcode::CALLVALUE => Some(call.value),
+            opcode::CALLDATALOAD => {
+                let offset = pop(&mut stack)?;
+                let mut word = [0u8; 8];
+                let start = call.input.len().min(offset as usize);
+                let end = call.input.len().min(start + 8);
+                word[..end - start].copy_from_slice(&call.input[start..end]);
+                Some(u64::from_be_bytes(word))
+            }
+            opcode::CALLDATASIZE => Some(call.input.len() as u64),
+            opcode::RETURN => {
+                execution.output = Some(pop(&mut stack)?);
+                return Ok(execution);
+            }
+            opcode::REVERT => anyhow::bail!("Reverted at {at}"),
+            op => anyhow::bail!("Invalid opcode {op:#04x} at {at}"),
+        };
+
+        if let Some(word) = push {
+            if stack.len() == MAX_STACK_SIZE {
+                anyhow::bail!("Stack overflow at {at}");
+            }
+            stack.push(word);
+        }
+    }
+
+    // running past the end of the code stops successfully
+    Ok(execution)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vm::{Call, Execution, execute, opcode};
+    use std::collections::HashMap;
+
+    fn push(word: u64) -> Vec<u8> {
+        [&[opcode::PUSH][..], &word.to_be_bytes()].concat()
+    }
+
+    fn run(code: &[u8], input: &[u8], gas_limit: u64) -> anyhow::Result<Execution> {
+        let call = Call {
+            code,
+            input,
+            value: 7,
+            gas_limit,
+        };
+        execute(&call, |key| key * 10)
+    }
+
+    #[test]
+    fn arithmetic() {
+        let code = [
+            push(6),
+            push(4),
+            vec![opcode::SUB],
+            push(5),
+            vec![opcode::MUL, opcode::CALLVALUE, opcode::ADD, opcode::RETURN],
+        ]
+        .concat();
+        let execution = run(&code, &[], 100).unwrap();
+        assert_eq!(execution.output, Some(17));
+        assert_eq!(execution.gas_used, 10);
+
+        let code = [push(1), push(0), vec![opcode::DIV]].concat();
+        assert!(run(&code, &[], 100).is_err());
+    }
+
+    #[test]
+    fn storage() {
+        // store the first input word at key 1, then read back keys 1 and 2
+        let code = [
+            push(1),
+            push(0),
+            vec![opcode::CALLDATALOAD, opcode::SSTORE],
+            push(1),
+            vec![opcode::SLOAD],
+            push(2),
+            vec![opcode::SLOAD, opcode::ADD, opcode::RETURN],
+        ]
+        .concat();
+        let execution = run(&code, &42u64.to_be_bytes(), 1_000).unwrap();
+        assert_eq!(execution.output, Some(42 + 20));
+        assert_eq!(execution.writes, HashMap::from([(1, 42)]));
+    }
+
+    #[test]
+    fn metered_loop() {
+        // an endless loop runs out of gas
+        let code = [push(0), vec![opcode::JUMP]].concat();
+        assert!(run(&code, &[], 1_000).is_err());
+
+        // count down from the input, jumping back while the counter is not zero
+        let code = [
+            push(0),
+            vec![opcode::CALLDATALOAD],
+            // 10: loop
+            push(1),
+            vec![opcode::SUB, opcode::DUP],
+            push(10),
+            vec![opcode::SWAP, opcode::JUMPI, opcode::RETURN],
+        ]
+        .concat();
+        let execution = run(&code, &3u64.to_be_bytes(), 1_000).unwrap();
+        assert_eq!(execution.output, Some(0));
+    }
+
+    #[test]
+    fn reject_invalid_code() {
+        assert!(run(&[opcode::ADD], &[], 100).is_err(), "stack underflow");
+        assert!(run(&[opcode::PUSH, 0], &[], 100).is_err(), "truncated push");
+        assert!(run(&[0xee], &[], 100).is_err(), "invalid opcode");
+        assert!(run(&[opcode::REVERT], &[], 100).is_err());
+        let jump = [push(100), vec![opcode::JUMP]].concat();
+        assert!(run(&jump, &[], 100).is_err(), "jump out of the code");
+        assert_eq!(run(&[], &[], 0).unwrap().gas_used, 0);
+    }
+}
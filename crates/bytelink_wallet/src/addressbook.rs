@@ -0,0 +1,166 @@
+use crate::keyring::validate_label;
+use anyhow::Result;
+use bytelink_crypto::address::Address;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryKind {
+    /// an address of someone else, such as a customer or an exchange
+    Contact,
+    /// an address we track without holding its key
+    Watch,
+}
+
+/// A labelled address of the [`AddressBook`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    pub label: String,
+    pub address: Address,
+    pub kind: EntryKind,
+}
+
+/// Labelled addresses the wallet holds no key for, saved as a JSON file.
+/// A label can be used on the command line wherever an address is expected.
+pub struct AddressBook {
+    path: PathBuf,
+    entries: Vec<Entry>,
+}
+
+impl AddressBook {
+    /// Open the address book file, which is created on the first change if missing.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(AddressBook { path, entries })
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// Add a labelled address, which must be [EIP-55](https://eips.ethereum.org/EIPS/eip-55) checksummed.
+    pub fn add(&mut self, label: &str, address: &str, kind: EntryKind) -> Result<&Entry> {
+        validate_label(label)?;
+        let address = Address::parse_checksummed(address)?;
+        if let Some(entry) = self
+            .entries
+            .iter()
+            .find(|e| e.label == label || e.address == address)
+        {
+            anyhow::bail!(
+                "Address book entry already exists: {} is {}",
+                entry.address.checksummed(),
+                entry.label
+            );
+        }
+
+        self.entries.push(Entry {
+            label: label.to_string(),
+            address,
+            kind,
+        });
+        self.save()?;
+        Ok(self.entries.last().expect("entry was just pushed"))
+    }
+
+    pub fn remove(&mut self, label: &str) -> Result<Entry> {
+        let position = self
+            .entries
+            .iter()
+            .position(|e| e.label == label)
+            .ok_or_else(|| anyhow::anyhow!("Unknown address book entry: {label}"))?;
+        let entry = self.entries.remove(position);
+        self.save()?;
+        Ok(entry)
+    }
+
+    /// Resolve a checksummed address or the label of an entry into an address.
+    pub fn resolve(&self, name: &str) -> Result<Address> {
+        if let Ok(address) = Address::parse_checksummed(name) {
+            return Ok(address);
+        }
+        self.entries
+            .iter()
+            .find(|e| e.label == name)
+            .map(|e| e.address.clone())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Invalid address: {name} is neither a checksummed address nor a label"
+                )
+            })
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::File::create(&self.path)?;
+        file.write_all(serde_json::to_string_pretty(&self.entries)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::addressbook::{AddressBook, EntryKind};
+    use std::path::PathBuf;
+
+    const EXCHANGE: &str = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+    const TREASURY: &str = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8";
+
+    fn temp_file() -> PathBuf {
+        std::env::temp_dir().join(format!("bytelink-book-{}.json", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn manage_entries() {
+        let path = temp_file();
+
+        let mut book = AddressBook::open(&path).unwrap();
+        book.add("exchange", EXCHANGE, EntryKind::Contact).unwrap();
+        book.add("treasury", TREASURY, EntryKind::Watch).unwrap();
+
+        // the entries are persisted
+        let mut book = AddressBook::open(&path).unwrap();
+        assert_eq!(book.entries().len(), 2);
+        assert_eq!(book.entries()[1].kind, EntryKind::Watch);
+        assert_eq!(book.resolve("exchange").unwrap().checksummed(), EXCHANGE);
+        assert_eq!(book.resolve(TREASURY).unwrap().checksummed(), TREASURY);
+
+        book.remove("exchange").unwrap();
+        assert!(book.resolve("exchange").is_err());
+        assert!(book.remove("exchange").is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reject_invalid_entries() {
+        let mut book = AddressBook::open(temp_file()).unwrap();
+
+        // wrong checksum
+        let wrong = EXCHANGE.replace("0xf39F", "0xF39F");
+        assert!(book.add("exchange", &wrong, EntryKind::Contact).is_err());
+        // a label must not look like an address
+        assert!(book.add(TREASURY, EXCHANGE, EntryKind::Contact).is_err());
+        assert!(book.add("", EXCHANGE, EntryKind::Contact).is_err());
+        assert!(book.resolve("nobody").is_err());
+    }
+
+    #[test]
+    fn reject_duplicates() {
+        let path = temp_file();
+        let mut book = AddressBook::open(&path).unwrap();
+        book.add("exchange", EXCHANGE, EntryKind::Contact).unwrap();
+        assert!(book.add("exchange", TREASURY, EntryKind::Watch).is_err());
+        assert!(book.add("other", EXCHANGE, EntryKind::Watch).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,5 +1,8 @@
 #[derive(clap::Parser)]
 pub struct Cli {
+    #[clap(long, global = true)]
+    /// Address book file, defaults to ~/.bytelink/addressbook.json
+    pub address_book: Option<String>,
     #[clap(subcommand)]
     pub cmd: Command,
 }
@@ -8,6 +11,89 @@ pub struct Cli {
 pub enum Command {
     /// Generate a new secp256k1 wallet
     Create(CreateOpts),
+    /// Encrypt a plaintext hex private key into a keystore file
+    Import(ImportOpts),
+    /// Decrypt a keystore file back into a plaintext hex private key
+    Decrypt(DecryptOpts),
+    /// Generate a new BIP-39 mnemonic phrase
+    Mnemonic(MnemonicOpts),
+    /// List the addresses derived from a mnemonic phrase
+    Addresses(AddressesOpts),
+    /// Restore the wallet derived from a mnemonic phrase into a keystore file
+    Restore(RestoreOpts),
+    /// Sign a message with a wallet, using the bytelink EIP-191 prefix
+    SignMessage(SignMessageOpts),
+    /// Check that a message signature was made by an address
+    VerifyMessage(VerifyMessageOpts),
+    /// Build and sign transactions offline
+    #[clap(subcommand)]
+    Tx(TxCommand),
+    /// Derive multisig accounts and collect the signatures of their owners
+    #[clap(subcommand)]
+    Multisig(MultisigCommand),
+    /// Manage the named accounts of a keyring directory
+    Keyring(KeyringOpts),
+    /// Search for a key whose address matches a pattern
+    Vanity(VanityOpts),
+    /// Run a signing daemon on a local socket, holding the key for remote signers
+    #[cfg(all(unix, feature = "remote-signer"))]
+    Signer(SignerDaemonOpts),
+    /// Manage the contacts and watch-only addresses of the address book
+    #[clap(subcommand)]
+    Book(BookCommand),
+}
+
+#[derive(clap::Subcommand)]
+pub enum BookCommand {
+    /// List the address book entries
+    List,
+    /// Add a labelled address
+    Add {
+        /// Label of the address, usable wherever an address is expected
+        label: String,
+        /// Checksummed address
+        address: String,
+        #[clap(long)]
+        /// Track the address as a watch-only account instead of a contact
+        watch: bool,
+    },
+    /// Remove a labelled address
+    Remove {
+        /// Label of the address
+        label: String,
+    },
+}
+
+#[derive(clap::Subcommand)]
+pub enum TxCommand {
+    /// Build an unsigned transaction as JSON
+    Build(TxBuildOpts),
+    /// Sign an unsigned transaction, writing the hex of the signed transaction
+    Sign(TxSignOpts),
+}
+
+#[derive(clap::Subcommand)]
+pub enum MultisigCommand {
+    /// Print the address of a multisig account
+    Address(MultisigOpts),
+    /// Sign an unsigned transaction as one owner, writing the partially signed transaction JSON
+    Sign(MultisigSignOpts),
+    /// Combine the signatures of partially signed transactions
+    Combine(MultisigCombineOpts),
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Default)]
+pub enum KdfKind {
+    #[default]
+    Scrypt,
+    Pbkdf2,
+}
+
+#[derive(clap::Parser)]
+pub struct PasswordOpts {
+    #[clap(long)]
+    /// Read the keystore password from a file instead of prompting for it
+    pub password_file: Option<String>,
 }
 
 #[derive(clap::Parser)]
@@ -15,4 +101,364 @@ pub struct CreateOpts {
     #[clap(long, short)]
     /// Output file to save the wallet's private key
     pub output: String,
+    #[clap(long)]
+    /// Save the private key as plaintext hex instead of an encrypted keystore
+    pub plaintext: bool,
+    #[clap(long, value_enum, default_value_t)]
+    /// Key derivation function protecting the keystore password
+    pub kdf: KdfKind,
+    #[clap(flatten)]
+    pub password: PasswordOpts,
+}
+
+#[derive(clap::Parser)]
+pub struct ImportOpts {
+    #[clap(long, short)]
+    /// File containing the plaintext hex private key
+    pub input: String,
+    #[clap(long, short)]
+    /// Output file to save the encrypted keystore
+    pub output: String,
+    #[clap(long, value_enum, default_value_t)]
+    /// Key derivation function protecting the keystore password
+    pub kdf: KdfKind,
+    #[clap(flatten)]
+    pub password: PasswordOpts,
+}
+
+#[derive(clap::Parser)]
+pub struct DecryptOpts {
+    #[clap(long, short)]
+    /// Keystore file to decrypt
+    pub input: String,
+    #[clap(long, short)]
+    /// Output file to save the plaintext hex private key, printed if omitted
+    pub output: Option<String>,
+    #[clap(flatten)]
+    pub password: PasswordOpts,
+}
+
+#[derive(clap::Parser)]
+pub struct MnemonicOpts {
+    #[clap(long, default_value_t = 12)]
+    /// Number of words of the mnemonic phrase, 12 or 24
+    pub words: usize,
+    #[clap(long, short)]
+    /// Output file to save the mnemonic phrase, printed if omitted
+    pub output: Option<String>,
+}
+
+#[derive(clap::Parser)]
+pub struct SeedOpts {
+    #[clap(long)]
+    /// Read the mnemonic phrase from a file instead of prompting for it
+    pub mnemonic_file: Option<String>,
+    #[clap(long)]
+    /// Read the BIP-39 passphrase from a file, no passphrase if omitted
+    pub passphrase_file: Option<String>,
+    #[clap(long, default_value = crate::hd::DEFAULT_PATH)]
+    /// Derivation path of the account, the address index is appended to it
+    pub path: crate::hd::DerivationPath,
+}
+
+#[derive(clap::Parser)]
+pub struct AddressesOpts {
+    #[clap(long, short, default_value_t = 10)]
+    /// Number of addresses to list
+    pub count: u32,
+    #[clap(flatten)]
+    pub seed: SeedOpts,
+}
+
+#[derive(clap::Parser)]
+pub struct RestoreOpts {
+    #[clap(long, default_value_t = 0)]
+    /// Address index of the wallet to restore
+    pub index: u32,
+    #[clap(long, short)]
+    /// Output file to save the encrypted keystore
+    pub output: String,
+    #[clap(long, value_enum, default_value_t)]
+    /// Key derivation function protecting the keystore password
+    pub kdf: KdfKind,
+    #[clap(flatten)]
+    pub seed: SeedOpts,
+    #[clap(flatten)]
+    pub password: PasswordOpts,
+}
+
+#[derive(clap::Parser)]
+#[group(required = true, multiple = false)]
+pub struct MessageOpts {
+    #[clap(long, short)]
+    /// The message text
+    pub message: Option<String>,
+    #[clap(long)]
+    /// Read the message from a file, as raw bytes
+    pub message_file: Option<String>,
+}
+
+#[derive(clap::Parser)]
+#[group(required = true, multiple = false)]
+pub struct SignerOpts {
+    #[clap(long, short)]
+    /// Key file of the signer, either a keystore or a plaintext hex private key
+    pub key: Option<String>,
+    #[cfg(all(unix, feature = "remote-signer"))]
+    #[clap(long)]
+    /// Socket of a signing daemon holding the key, see the signer command
+    pub remote: Option<String>,
+}
+
+#[derive(clap::Parser)]
+pub struct SignMessageOpts {
+    #[clap(flatten)]
+    pub signer: SignerOpts,
+    #[clap(flatten)]
+    pub message: MessageOpts,
+    #[clap(flatten)]
+    pub password: PasswordOpts,
+}
+
+#[derive(clap::Parser)]
+pub struct VerifyMessageOpts {
+    #[clap(long, short)]
+    /// Checksummed address or address book label of the expected signer
+    pub address: String,
+    #[clap(long, short)]
+    /// Hex of the 65-byte signature
+    pub signature: String,
+    #[clap(flatten)]
+    pub message: MessageOpts,
+}
+
+#[derive(clap::Parser)]
+pub struct TxBuildOpts {
+    #[clap(long)]
+    /// Chain id of the network the transaction is meant for
+    pub chain_id: u16,
+    #[clap(long)]
+    /// Nonce of the sender account
+    pub nonce: u64,
+    #[clap(long, required_unless_present = "create")]
+    /// Checksummed address or address book label of the recipient
+    pub to: Option<String>,
+    #[clap(long, conflicts_with = "to")]
+    /// Deploy the data payload as the code of a new contract, sent by this address.
+    /// The recipient is the address of the new contract
+    pub create: Option<String>,
+    #[clap(long, requires = "create")]
+    /// Create a token with this fixed supply instead of a contract, its id is the recipient
+    pub supply: Option<u64>,
+    #[clap(long, requires = "amount", conflicts_with = "create")]
+    /// Checksummed id or address book label of the token to send to the recipient
+    pub token: Option<String>,
+    #[clap(long, requires = "token")]
+    /// Amount of the token
+    pub amount: Option<u64>,
+    #[clap(long, requires = "token")]
+    /// Allow the recipient to spend the token amount instead of sending it
+    pub approve: bool,
+    #[clap(long, requires = "token", conflicts_with = "approve")]
+    /// Send the token amount out of the allowance given by this address
+    pub from: Option<String>,
+    #[clap(long, requires = "timeout", conflicts_with_all = ["create", "token"])]
+    /// Hex of the Keccak-256 hashlock, to lock the value in an HTLC for the recipient
+    pub hashlock: Option<String>,
+    #[clap(long, requires = "hashlock")]
+    /// Block height or RFC 3339 timestamp after which the HTLC can no longer be claimed
+    pub timeout: Option<bytelink_node::database::timelock::TimeLock>,
+    #[clap(long, conflicts_with_all = ["create", "token", "hashlock"])]
+    /// Claim the HTLC whose id is the recipient, the data payload being the preimage
+    pub claim: bool,
+    #[clap(long, conflicts_with_all = ["create", "token", "hashlock", "claim"])]
+    /// Refund the expired HTLC whose id is the recipient
+    pub refund: bool,
+    #[clap(long)]
+    /// Amount to send
+    pub value: u64,
+    #[clap(long, default_value_t = 0)]
+    /// Rewards for processing the transaction
+    pub tip: u64,
+    #[clap(long, conflicts_with = "data_file")]
+    /// Hex of the data payload
+    pub data: Option<String>,
+    #[clap(long)]
+    /// Read the data payload from a file, as raw bytes
+    pub data_file: Option<String>,
+    #[clap(long)]
+    /// Block height or RFC 3339 timestamp before which the transaction cannot be included
+    pub valid_after: Option<bytelink_node::database::timelock::TimeLock>,
+    #[clap(long)]
+    /// Block height or RFC 3339 timestamp after which the transaction cannot be included
+    pub valid_until: Option<bytelink_node::database::timelock::TimeLock>,
+    #[clap(long, short)]
+    /// Output file to save the unsigned transaction, printed if omitted
+    pub output: Option<String>,
+}
+
+#[derive(clap::Parser)]
+pub struct TxSignOpts {
+    #[clap(flatten)]
+    pub signer: SignerOpts,
+    #[clap(long, short)]
+    /// File containing the unsigned transaction JSON
+    pub input: String,
+    #[clap(long, short)]
+    /// Output file to save the signed transaction, printed if omitted
+    pub output: Option<String>,
+    #[clap(long)]
+    /// Write the signed transaction as JSON instead of the hex of its binary form
+    pub json: bool,
+    #[clap(flatten)]
+    pub password: PasswordOpts,
+}
+
+#[derive(clap::Parser)]
+pub struct MultisigOpts {
+    #[clap(long = "owner", required = true)]
+    /// Checksummed address or address book label of an owner, repeated for every owner
+    pub owners: Vec<String>,
+    #[clap(long)]
+    /// Number of owner signatures required by every transaction
+    pub threshold: u8,
+}
+
+#[derive(clap::Parser)]
+pub struct MultisigSignOpts {
+    #[clap(flatten)]
+    pub multisig: MultisigOpts,
+    #[clap(flatten)]
+    pub signer: SignerOpts,
+    #[clap(long, short)]
+    /// File containing the unsigned transaction JSON
+    pub input: String,
+    #[clap(long, short)]
+    /// Output file to save the partially signed transaction, printed if omitted
+    pub output: Option<String>,
+    #[clap(flatten)]
+    pub password: PasswordOpts,
+}
+
+#[derive(clap::Parser)]
+pub struct MultisigCombineOpts {
+    #[clap(long, short, required = true)]
+    /// File containing a partially signed transaction JSON, repeated for every file
+    pub input: Vec<String>,
+    #[clap(long, short)]
+    /// Output file to save the signed transaction, printed if omitted
+    pub output: Option<String>,
+    #[clap(long)]
+    /// Write the signed transaction as JSON instead of the hex of its binary form
+    pub json: bool,
+}
+
+#[derive(clap::Parser)]
+pub struct KeyringOpts {
+    #[clap(long)]
+    /// Keyring directory, defaults to ~/.bytelink/keyring
+    pub dir: Option<String>,
+    #[clap(subcommand)]
+    pub cmd: KeyringCommand,
+}
+
+#[derive(clap::Subcommand)]
+pub enum KeyringCommand {
+    /// List the accounts, the default one is marked with "*"
+    List,
+    /// Add a key file, either a keystore or a plaintext hex private key
+    Import(KeyringImportOpts),
+    /// Write the encrypted keystore of an account
+    Export(KeyringExportOpts),
+    /// Change the label of an account
+    Rename {
+        /// Label or checksummed address of the account
+        account: String,
+        /// New label of the account
+        label: String,
+    },
+    /// Remove an account and delete its key
+    Remove {
+        /// Label or checksummed address of the account
+        account: String,
+    },
+    /// Show the default account, or set it
+    Default {
+        /// Label or checksummed address of the new default account
+        account: Option<String>,
+    },
+}
+
+#[derive(clap::Parser)]
+pub struct KeyringImportOpts {
+    #[clap(long, short)]
+    /// Key file to import, either a keystore or a plaintext hex private key
+    pub input: String,
+    #[clap(long, short)]
+    /// Label of the account
+    pub label: String,
+    #[clap(long, value_enum, default_value_t)]
+    /// Key derivation function protecting the password of a plaintext key
+    pub kdf: KdfKind,
+    #[clap(flatten)]
+    pub password: PasswordOpts,
+}
+
+#[derive(clap::Parser)]
+pub struct KeyringExportOpts {
+    /// Label or checksummed address of the account, the default one if omitted
+    pub account: Option<String>,
+    #[clap(long, short)]
+    /// Output file to save the keystore, printed if omitted
+    pub output: Option<String>,
+}
+
+#[derive(clap::Parser)]
+#[group(id = "pattern", required = true, multiple = true)]
+pub struct PatternOpts {
+    #[clap(long)]
+    /// Hex characters the address must start with
+    pub prefix: Option<String>,
+    #[clap(long)]
+    /// Hex characters the address must end with
+    pub suffix: Option<String>,
+    #[clap(long)]
+    /// Regex the 40 hex characters of the address must match
+    pub regex: Option<String>,
+}
+
+#[derive(clap::Parser)]
+pub struct VanityOpts {
+    #[clap(flatten)]
+    pub pattern: PatternOpts,
+    #[clap(long)]
+    /// Match against the EIP-55 checksummed address instead of ignoring case
+    pub case_sensitive: bool,
+    #[clap(long, short)]
+    /// Number of threads searching, all the available cores if omitted
+    pub threads: Option<usize>,
+    #[clap(long, short)]
+    /// Output file to save the wallet's private key
+    pub output: String,
+    #[clap(long)]
+    /// Save the private key as plaintext hex instead of an encrypted keystore
+    pub plaintext: bool,
+    #[clap(long, value_enum, default_value_t)]
+    /// Key derivation function protecting the keystore password
+    pub kdf: KdfKind,
+    #[clap(flatten)]
+    pub password: PasswordOpts,
+}
+
+#[cfg(all(unix, feature = "remote-signer"))]
+#[derive(clap::Parser)]
+pub struct SignerDaemonOpts {
+    #[clap(long, short)]
+    /// Key file of the signer, either a keystore or a plaintext hex private key
+    pub key: String,
+    #[clap(long, short)]
+    /// Path of the socket to listen on, only the current user can connect to it
+    pub socket: String,
+    #[clap(flatten)]
+    pub password: PasswordOpts,
 }
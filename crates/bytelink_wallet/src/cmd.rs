@@ -1,16 +1,858 @@
-use crate::cli::CreateOpts;
+use crate::addressbook::{AddressBook, EntryKind};
+#[cfg(all(unix, feature = "remote-signer"))]
+use crate::cli::SignerDaemonOpts;
+use crate::cli::{
+    AddressesOpts, BookCommand, Cli, Command, CreateOpts, DecryptOpts, ImportOpts, KdfKind,
+    KeyringCommand, KeyringExportOpts, KeyringImportOpts, KeyringOpts, MessageOpts, MnemonicOpts,
+    MultisigCombineOpts, MultisigCommand, MultisigOpts, MultisigSignOpts, PasswordOpts,
+    RestoreOpts, SeedOpts, SignMessageOpts, SignerOpts, TxBuildOpts, TxCommand, TxSignOpts,
+    VanityOpts, VerifyMessageOpts,
+};
+use crate::hd::generate_mnemonic;
+use crate::keyring::Keyring;
+use crate::keystore::{Kdf, Keystore, create_secret_file};
+use crate::vanity::Pattern;
 use crate::wallet::Wallet;
+use alloy_primitives::B256;
 use anyhow::Result;
+use bytelink_crypto::address::Address;
+#[cfg(all(unix, feature = "remote-signer"))]
+use bytelink_crypto::remote::RemoteSigner;
+use bytelink_crypto::signature::Signature;
+use bytelink_crypto::signer::AsyncSigner;
+use bytelink_node::database::multisig::Multisig;
+use bytelink_node::database::transaction::{SignedTx, Transaction, TxKind};
+use bytelink_node::vm::contract_address;
+use std::cell::OnceCell;
 use std::io::Write;
 
+/// Run the command of the parsed command line.
+pub fn run(cli: Cli) -> Result<()> {
+    let book = cli.address_book.as_deref();
+
+    match cli.cmd {
+        Command::Create(opts) => generate_wallet(&opts),
+        Command::Import(opts) => import_wallet(&opts),
+        Command::Decrypt(opts) => decrypt_wallet(&opts),
+        Command::Mnemonic(opts) => generate_mnemonic_phrase(&opts),
+        Command::Addresses(opts) => list_addresses(&opts),
+        Command::Restore(opts) => restore_wallet(&opts),
+        Command::SignMessage(opts) => sign_message(&opts),
+        Command::VerifyMessage(opts) => verify_message(&opts, book),
+        Command::Tx(TxCommand::Build(opts)) => build_transaction(&opts, book),
+        Command::Tx(TxCommand::Sign(opts)) => sign_transaction(&opts),
+        Command::Multisig(MultisigCommand::Address(opts)) => multisig_address(&opts, book),
+        Command::Multisig(MultisigCommand::Sign(opts)) => sign_multisig(&opts, book),
+        Command::Multisig(MultisigCommand::Combine(opts)) => combine_multisig(&opts),
+        Command::Keyring(opts) => manage_keyring(&opts),
+        Command::Vanity(opts) => generate_vanity(&opts),
+        #[cfg(all(unix, feature = "remote-signer"))]
+        Command::Signer(opts) => run_signer(&opts),
+        Command::Book(book_cmd) => manage_address_book(&book_cmd, book),
+    }
+}
+
+/// The directory of the wallet data, `~/.bytelink`.
+fn home_dir() -> Result<std::path::PathBuf> {
+    let home = std::env::home_dir()
+        .ok_or_else(|| anyhow::anyhow!("Missing home directory, pass the path explicitly"))?;
+    Ok(home.join(".bytelink"))
+}
+
+/// Open the address book file if any, the default one otherwise.
+fn open_address_book(path: Option<&str>) -> Result<AddressBook> {
+    match path {
+        Some(path) => AddressBook::open(path),
+        None => AddressBook::open(home_dir()?.join("addressbook.json")),
+    }
+}
+
+/// Resolve addresses or labels, opening the address book only once a label has to be looked up.
+struct Resolver<'a> {
+    path: Option<&'a str>,
+    book: OnceCell<AddressBook>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(path: Option<&'a str>) -> Self {
+        Resolver {
+            path,
+            book: OnceCell::new(),
+        }
+    }
+
+    fn resolve(&self, name: &str) -> Result<Address> {
+        if let Ok(address) = Address::parse_checksummed(name) {
+            return Ok(address);
+        }
+        let book = match self.book.get() {
+            Some(book) => book,
+            None => {
+                let book = open_address_book(self.path)?;
+                self.book.get_or_init(|| book)
+            }
+        };
+        book.resolve(name)
+    }
+}
+
+/// Read the password from the password file if any, prompt for it otherwise.
+/// A new password is prompted twice to catch typos.
+fn read_password(opts: &PasswordOpts, confirm: bool) -> Result<String> {
+    if let Some(path) = &opts.password_file {
+        let content = std::fs::read_to_string(path)?;
+        return Ok(content.trim_end_matches(['\r', '\n']).to_string());
+    }
+
+    let password = rpassword::prompt_password("Password: ")?;
+    if confirm && rpassword::prompt_password("Repeat password: ")? != password {
+        anyhow::bail!("Passwords do not match");
+    }
+    Ok(password)
+}
+
+/// Load a wallet from a key file, either an encrypted keystore or a plaintext hex private key.
+pub fn load_wallet(path: &str, password: &PasswordOpts) -> Result<Wallet> {
+    let content = std::fs::read_to_string(path)?;
+    match Keystore::from_key_file(&content)? {
+        Some(keystore) => Wallet::from_keystore(&keystore, &read_password(password, false)?),
+        None => Wallet::from_hex(&content),
+    }
+}
+
+/// Read the mnemonic phrase and its passphrase from files if any, prompt for the phrase otherwise.
+fn read_seed(opts: &SeedOpts) -> Result<(String, String)> {
+    let phrase = match &opts.mnemonic_file {
+        Some(path) => std::fs::read_to_string(path)?,
+        None => rpassword::prompt_password("Mnemonic: ")?,
+    };
+    let passphrase = match &opts.passphrase_file {
+        Some(path) => std::fs::read_to_string(path)?
+            .trim_end_matches(['\r', '\n'])
+            .to_string(),
+        None => String::new(),
+    };
+    Ok((phrase.trim().to_string(), passphrase))
+}
+
+/// Run a future to completion on a single threaded runtime.
+fn block_on<F: Future>(future: F) -> Result<F::Output> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    Ok(runtime.block_on(future))
+}
+
+/// The signer of a command, either a key file or a signing daemon.
+enum KeySigner {
+    Local(Wallet),
+    #[cfg(all(unix, feature = "remote-signer"))]
+    Remote(RemoteSigner),
+}
+
+impl KeySigner {
+    async fn load(opts: &SignerOpts, password: &PasswordOpts) -> Result<Self> {
+        if let Some(key) = &opts.key {
+            return Ok(KeySigner::Local(load_wallet(key, password)?));
+        }
+        #[cfg(all(unix, feature = "remote-signer"))]
+        if let Some(socket) = &opts.remote {
+            return Ok(KeySigner::Remote(RemoteSigner::connect(socket).await?));
+        }
+        anyhow::bail!("Missing signer: use --key or --remote")
+    }
+}
+
+impl AsyncSigner for KeySigner {
+    async fn sign_hash(&self, hash: &B256) -> Result<Signature> {
+        match self {
+            KeySigner::Local(wallet) => wallet.sign_hash(hash).await,
+            #[cfg(all(unix, feature = "remote-signer"))]
+            KeySigner::Remote(remote) => remote.sign_hash(hash).await,
+        }
+    }
+}
+
+fn read_message(opts: &MessageOpts) -> Result<Vec<u8>> {
+    match (&opts.message, &opts.message_file) {
+        (Some(message), _) => Ok(message.as_bytes().to_vec()),
+        (None, Some(path)) => Ok(std::fs::read(path)?),
+        (None, None) => anyhow::bail!("Missing message: use --message or --message-file"),
+    }
+}
+
+/// Write the content to the output file if any, print it otherwise.
+fn write_or_print(output: Option<&str>, content: &str) -> Result<()> {
+    match output {
+        Some(output) => {
+            let mut file = std::fs::File::create(output)?;
+            file.write_all(content.as_bytes())?;
+        }
+        None => println!("{content}"),
+    }
+    Ok(())
+}
+
+/// Same as [`write_or_print`], the output file being readable by the owner only.
+fn write_secret_or_print(output: Option<&str>, content: &str) -> Result<()> {
+    match output {
+        Some(output) => create_secret_file(output)?.write_all(content.as_bytes())?,
+        None => println!("{content}"),
+    }
+    Ok(())
+}
+
+impl From<KdfKind> for Kdf {
+    fn from(kind: KdfKind) -> Self {
+        match kind {
+            KdfKind::Scrypt => Kdf::scrypt(),
+            KdfKind::Pbkdf2 => Kdf::pbkdf2(),
+        }
+    }
+}
+
+fn save_keystore(
+    wallet: &Wallet,
+    output: &str,
+    kdf: KdfKind,
+    password: &PasswordOpts,
+) -> Result<()> {
+    let keystore = wallet.to_keystore(&read_password(password, true)?, kdf.into())?;
+    let mut file = create_secret_file(output)?;
+    file.write_all(keystore.to_json()?.as_bytes())?;
+    Ok(())
+}
+
+/// Save a new wallet either as plaintext hex or as an encrypted keystore.
+fn save_wallet(
+    wallet: &Wallet,
+    output: &str,
+    plaintext: bool,
+    kdf: KdfKind,
+    password: &PasswordOpts,
+) -> Result<()> {
+    if plaintext {
+        let mut file = create_secret_file(output)?;
+        file.write_all(wallet.as_hex().as_bytes())?;
+        Ok(())
+    } else {
+        save_keystore(wallet, output, kdf, password)
+    }
+}
+
 pub fn generate_wallet(opts: &CreateOpts) -> Result<()> {
     let wallet = Wallet::random();
 
     println!("Wallet generated: {}", wallet.address().checksummed());
 
-    let mut file = std::fs::File::create(&opts.output)?;
+    save_wallet(
+        &wallet,
+        &opts.output,
+        opts.plaintext,
+        opts.kdf,
+        &opts.password,
+    )
+}
+
+pub fn import_wallet(opts: &ImportOpts) -> Result<()> {
+    let wallet = Wallet::from_hex(&std::fs::read_to_string(&opts.input)?)?;
+
+    save_keystore(&wallet, &opts.output, opts.kdf, &opts.password)?;
+
+    println!("Wallet imported: {}", wallet.address().checksummed());
+
+    Ok(())
+}
+
+pub fn decrypt_wallet(opts: &DecryptOpts) -> Result<()> {
+    let wallet = load_wallet(&opts.input, &opts.password)?;
+
+    match &opts.output {
+        Some(output) => {
+            let mut file = create_secret_file(output)?;
+            file.write_all(wallet.as_hex().as_bytes())?;
+            println!("Wallet decrypted: {}", wallet.address().checksummed());
+        }
+        None => println!("{}", wallet.as_hex()),
+    }
+
+    Ok(())
+}
+
+pub fn generate_mnemonic_phrase(opts: &MnemonicOpts) -> Result<()> {
+    let mnemonic = generate_mnemonic(opts.words)?;
+    let phrase = mnemonic.to_string();
+    let wallet = Wallet::from_mnemonic(&phrase, "", &crate::hd::DEFAULT_PATH.parse()?)?;
 
-    file.write_all(wallet.as_hex().as_bytes())?;
+    write_secret_or_print(opts.output.as_deref(), &phrase)?;
+    println!("First address: {}", wallet.address().checksummed());
 
     Ok(())
 }
+
+pub fn list_addresses(opts: &AddressesOpts) -> Result<()> {
+    let (phrase, passphrase) = read_seed(&opts.seed)?;
+
+    for index in 0..opts.count {
+        let path = opts.seed.path.child(index);
+        let wallet = Wallet::from_mnemonic(&phrase, &passphrase, &path)?;
+        println!("{path}\t{}", wallet.address().checksummed());
+    }
+
+    Ok(())
+}
+
+pub fn restore_wallet(opts: &RestoreOpts) -> Result<()> {
+    let (phrase, passphrase) = read_seed(&opts.seed)?;
+    let wallet = Wallet::from_mnemonic(&phrase, &passphrase, &opts.seed.path.child(opts.index))?;
+
+    save_keystore(&wallet, &opts.output, opts.kdf, &opts.password)?;
+
+    println!("Wallet restored: {}", wallet.address().checksummed());
+
+    Ok(())
+}
+
+pub fn sign_message(opts: &SignMessageOpts) -> Result<()> {
+    let msg = read_message(&opts.message)?;
+    let signature = block_on(async {
+        let signer = KeySigner::load(&opts.signer, &opts.password).await?;
+        signer.eip191_sign_msg_async(&msg).await
+    })??;
+
+    println!("{signature}");
+
+    Ok(())
+}
+
+pub fn verify_message(opts: &VerifyMessageOpts, book: Option<&str>) -> Result<()> {
+    let expected = Resolver::new(book).resolve(&opts.address)?;
+    let signature = Signature::from_hex(&opts.signature)?;
+    let recovered = signature.recover_address_from_msg(&read_message(&opts.message)?)?;
+
+    if recovered != expected {
+        anyhow::bail!(
+            "Invalid signature: signed by {}, expected {}",
+            recovered.checksummed(),
+            expected.checksummed()
+        );
+    }
+
+    println!("Signature valid: {}", recovered.checksummed());
+
+    Ok(())
+}
+
+pub fn build_transaction(opts: &TxBuildOpts, book: Option<&str>) -> Result<()> {
+    let data = match (&opts.data, &opts.data_file) {
+        (Some(data), _) => hex::decode(data.trim().trim_start_matches("0x"))?,
+        (None, Some(path)) => std::fs::read(path)?,
+        (None, None) => Vec::new(),
+    };
+
+    let book = Resolver::new(book);
+    let (to, kind) = match (&opts.to, &opts.create) {
+        (Some(to), _) => (book.resolve(to)?, recipient_kind(opts, &book)?),
+        (None, Some(sender)) => (
+            contract_address(&book.resolve(sender)?, opts.nonce),
+            match opts.supply {
+                Some(supply) => TxKind::CreateToken { supply },
+                None => TxKind::Create,
+            },
+        ),
+        (None, None) => anyhow::bail!("Missing recipient: use --to or --create"),
+    };
+
+    let transaction = Transaction {
+        nonce: opts.nonce,
+        to,
+        value: opts.value,
+        tip: opts.tip,
+        data,
+        kind,
+        valid_after: opts.valid_after,
+        valid_until: opts.valid_until,
+        chain_id: Some(opts.chain_id),
+    };
+
+    write_or_print(
+        opts.output.as_deref(),
+        &serde_json::to_string_pretty(&transaction)?,
+    )
+}
+
+/// The kind of a transaction sent to a recipient.
+fn recipient_kind(opts: &TxBuildOpts, book: &Resolver<'_>) -> Result<TxKind> {
+    if let (Some(hashlock), Some(timeout)) = (&opts.hashlock, opts.timeout) {
+        let hashlock: B256 = hashlock
+            .trim()
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Invalid hashlock: {hashlock}"))?;
+        return Ok(TxKind::HtlcLock { hashlock, timeout });
+    }
+    if opts.claim {
+        return Ok(TxKind::HtlcClaim);
+    }
+    if opts.refund {
+        return Ok(TxKind::HtlcRefund);
+    }
+    let (Some(token), Some(amount)) = (&opts.token, opts.amount) else {
+        return Ok(TxKind::Transfer);
+    };
+    let token = book.resolve(token)?;
+    Ok(match (&opts.from, opts.approve) {
+        (Some(from), _) => TxKind::TokenTransferFrom {
+            token,
+            from: book.resolve(from)?,
+            amount,
+        },
+        (None, true) => TxKind::TokenApprove { token, amount },
+        (None, false) => TxKind::TokenTransfer { token, amount },
+    })
+}
+
+pub fn sign_transaction(opts: &TxSignOpts) -> Result<()> {
+    let transaction: Transaction = serde_json::from_str(&std::fs::read_to_string(&opts.input)?)?;
+    let signed = block_on(async {
+        let signer = KeySigner::load(&opts.signer, &opts.password).await?;
+        transaction.sign_by_async(&signer).await
+    })??;
+    signed.verify()?;
+
+    let content = if opts.json {
+        serde_json::to_string_pretty(&signed)?
+    } else {
+        alloy_primitives::hex::encode_prefixed(signed.encode())
+    };
+    write_or_print(opts.output.as_deref(), &content)?;
+
+    if opts.output.is_some() {
+        println!("Transaction signed: {}", signed.hash());
+    }
+
+    Ok(())
+}
+
+fn load_multisig(opts: &MultisigOpts, book: Option<&str>) -> Result<Multisig> {
+    let book = Resolver::new(book);
+    let owners = opts
+        .owners
+        .iter()
+        .map(|owner| book.resolve(owner))
+        .collect::<Result<Vec<_>>>()?;
+    Multisig::new(opts.threshold, owners)
+}
+
+pub fn multisig_address(opts: &MultisigOpts, book: Option<&str>) -> Result<()> {
+    let multisig = load_multisig(opts, book)?;
+    println!("{}", multisig.address().checksummed());
+    Ok(())
+}
+
+pub fn sign_multisig(opts: &MultisigSignOpts, book: Option<&str>) -> Result<()> {
+    let multisig = load_multisig(&opts.multisig, book)?;
+    let transaction: Transaction = serde_json::from_str(&std::fs::read_to_string(&opts.input)?)?;
+    let signed = block_on(async {
+        let signer = KeySigner::load(&opts.signer, &opts.password).await?;
+        transaction.sign_multisig_async(multisig, &signer).await
+    })??;
+
+    write_or_print(
+        opts.output.as_deref(),
+        &serde_json::to_string_pretty(&signed)?,
+    )
+}
+
+pub fn combine_multisig(opts: &MultisigCombineOpts) -> Result<()> {
+    let mut signed: Option<SignedTx> = None;
+    for input in &opts.input {
+        let partial: SignedTx = serde_json::from_str(&std::fs::read_to_string(input)?)?;
+        signed = Some(match signed {
+            Some(signed) => signed.merge(&partial)?,
+            None => partial,
+        });
+    }
+    let signed = signed.ok_or_else(|| anyhow::anyhow!("Missing partially signed transaction"))?;
+    let multisig = signed
+        .multisig()
+        .ok_or_else(|| anyhow::anyhow!("Not a multisig transaction: {}", opts.input[0]))?;
+
+    // below the threshold, keep the JSON form to combine it with further signatures
+    let collected = signed.signatures().len();
+    if collected < multisig.threshold() as usize {
+        write_or_print(
+            opts.output.as_deref(),
+            &serde_json::to_string_pretty(&signed)?,
+        )?;
+        if opts.output.is_some() {
+            println!(
+                "Transaction partially signed: {collected} of {} signatures",
+                multisig.threshold()
+            );
+        }
+        return Ok(());
+    }
+
+    signed.verify()?;
+    let content = if opts.json {
+        serde_json::to_string_pretty(&signed)?
+    } else {
+        alloy_primitives::hex::encode_prefixed(signed.encode())
+    };
+    write_or_print(opts.output.as_deref(), &content)?;
+
+    if opts.output.is_some() {
+        println!("Transaction signed: {}", signed.hash());
+    }
+
+    Ok(())
+}
+
+pub fn manage_keyring(opts: &KeyringOpts) -> Result<()> {
+    let dir = match &opts.dir {
+        Some(dir) => dir.into(),
+        None => home_dir()?.join("keyring"),
+    };
+    let mut keyring = Keyring::open(dir)?;
+
+    match &opts.cmd {
+        KeyringCommand::List => {
+            let default = keyring.default_account().map(|a| a.address.clone());
+            for account in keyring.accounts() {
+                let marker = if Some(&account.address) == default.as_ref() {
+                    "*"
+                } else {
+                    " "
+                };
+                println!(
+                    "{marker} {}\t{}",
+                    account.address.checksummed(),
+                    account.label
+                );
+            }
+        }
+        KeyringCommand::Import(opts) => import_account(&mut keyring, opts)?,
+        KeyringCommand::Export(opts) => export_account(&keyring, opts)?,
+        KeyringCommand::Rename { account, label } => {
+            keyring.rename(account, label)?;
+            println!("Account renamed: {label}");
+        }
+        KeyringCommand::Remove { account } => {
+            let account = keyring.remove(account)?;
+            println!("Account removed: {}", account.address.checksummed());
+        }
+        KeyringCommand::Default { account: None } => match keyring.default_account() {
+            Some(account) => println!("{}\t{}", account.address.checksummed(), account.label),
+            None => anyhow::bail!("No default account"),
+        },
+        KeyringCommand::Default {
+            account: Some(account),
+        } => {
+            keyring.set_default(account)?;
+            println!("Default account: {account}");
+        }
+    }
+
+    Ok(())
+}
+
+fn import_account(keyring: &mut Keyring, opts: &KeyringImportOpts) -> Result<()> {
+    let content = std::fs::read_to_string(&opts.input)?;
+    let account = match Keystore::from_key_file(&content)? {
+        // a keystore is stored as is, still protected by its own password
+        Some(keystore) => {
+            let password = read_password(&opts.password, false)?;
+            keyring.import(&opts.label, keystore, &password)?
+        }
+        None => {
+            let wallet = Wallet::from_hex(&content)?;
+            let password = read_password(&opts.password, true)?;
+            let keystore = wallet.to_keystore(&password, opts.kdf.into())?;
+            keyring.import(&opts.label, keystore, &password)?
+        }
+    };
+
+    println!("Account imported: {}", account.address.checksummed());
+
+    Ok(())
+}
+
+fn export_account(keyring: &Keyring, opts: &KeyringExportOpts) -> Result<()> {
+    let name = match &opts.account {
+        Some(name) => name.clone(),
+        None => keyring
+            .default_account()
+            .ok_or_else(|| anyhow::anyhow!("No default account"))?
+            .label
+            .clone(),
+    };
+
+    write_secret_or_print(opts.output.as_deref(), &keyring.export(&name)?.to_json()?)
+}
+
+pub fn generate_vanity(opts: &VanityOpts) -> Result<()> {
+    let pattern = Pattern::new(
+        opts.pattern.prefix.as_deref(),
+        opts.pattern.suffix.as_deref(),
+        opts.pattern.regex.as_deref(),
+        opts.case_sensitive,
+    )?;
+    let threads = match opts.threads {
+        Some(threads) => threads,
+        None => std::thread::available_parallelism()?.get(),
+    };
+    let difficulty = pattern.difficulty();
+
+    let wallet = crate::vanity::search(
+        &pattern,
+        threads,
+        std::time::Duration::from_secs(1),
+        |attempts, elapsed| {
+            let rate = attempts as f64 / elapsed.as_secs_f64();
+            // the search is memoryless, the expected remaining time does not shrink
+            let eta = match difficulty {
+                Some(difficulty) => format!(", ~{:.0}s expected", difficulty / rate),
+                None => String::new(),
+            };
+            eprint!("\r{attempts} keys tried, {rate:.0} keys/s{eta}    ");
+        },
+    );
+    eprintln!();
+
+    println!("Wallet generated: {}", wallet.address().checksummed());
+
+    save_wallet(
+        &wallet,
+        &opts.output,
+        opts.plaintext,
+        opts.kdf,
+        &opts.password,
+    )
+}
+
+pub fn manage_address_book(cmd: &BookCommand, book: Option<&str>) -> Result<()> {
+    let mut book = open_address_book(book)?;
+
+    match cmd {
+        BookCommand::List => {
+            for entry in book.entries() {
+                let kind = match entry.kind {
+                    EntryKind::Contact => "contact",
+                    EntryKind::Watch => "watch",
+                };
+                println!("{}\t{kind}\t{}", entry.address.checksummed(), entry.label);
+            }
+        }
+        BookCommand::Add {
+            label,
+            address,
+            watch,
+        } => {
+            let kind = if *watch {
+                EntryKind::Watch
+            } else {
+                EntryKind::Contact
+            };
+            let entry = book.add(label, address, kind)?;
+            println!("Address added: {}", entry.address.checksummed());
+        }
+        BookCommand::Remove { label } => {
+            let entry = book.remove(label)?;
+            println!("Address removed: {}", entry.address.checksummed());
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(all(unix, feature = "remote-signer"))]
+pub fn run_signer(opts: &SignerDaemonOpts) -> Result<()> {
+    let wallet = load_wallet(&opts.key, &opts.password)?;
+    let signer = std::sync::Arc::new(wallet.signer()?);
+
+    block_on(async {
+        let listener = bytelink_crypto::remote::bind(&opts.socket)?;
+        println!(
+            "Signer listening: {} on {}",
+            signer.address().checksummed(),
+            opts.socket
+        );
+        bytelink_crypto::remote::serve(listener, signer).await
+    })?
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::Cli;
+    use crate::keystore::{Kdf, Keystore};
+    use crate::wallet::Wallet;
+    use anyhow::Result;
+    use bytelink_node::database::multisig::Multisig;
+    use bytelink_node::database::transaction::{SignedTx, Transaction};
+    use clap::Parser;
+    use std::path::PathBuf;
+
+    /// A scratch directory, removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("bytelink-cmd-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn path(&self, name: &str) -> String {
+            self.0.join(name).to_str().unwrap().to_string()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Run the wallet with the whitespace separated arguments, as from the command line.
+    fn wallet(args: &str) -> Result<()> {
+        let args = std::iter::once("wallet").chain(args.split_whitespace());
+        super::run(Cli::try_parse_from(args)?)
+    }
+
+    fn read_wallet(path: &str) -> Wallet {
+        Wallet::from_hex(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn read_json<T: serde::de::DeserializeOwned>(path: &str) -> T {
+        serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn sign_and_combine_multisig() {
+        let dir = TempDir::new();
+        let book = dir.path("book.json");
+        let mut owners = Vec::new();
+        for name in ["alice", "bob", "carol", "mallory"] {
+            let key = dir.path(name);
+            wallet(&format!("create --plaintext -o {key}")).unwrap();
+            let address = read_wallet(&key).address();
+            wallet(&format!("--address-book {book} book add {name} {address}")).unwrap();
+            owners.push(address);
+        }
+        let mallory = owners.pop().unwrap();
+        let tx = dir.path("tx.json");
+        let to = Wallet::random().address();
+        wallet(&format!(
+            "tx build --chain-id 1 --nonce 0 --to {to} --value 10 -o {tx}"
+        ))
+        .unwrap();
+
+        // the owners are resolved through the address book
+        let sign = |owner: &str| {
+            let (key, output) = (dir.path(owner), dir.path(&format!("{owner}.json")));
+            wallet(&format!(
+                "--address-book {book} multisig sign --owner alice --owner bob --owner carol \
+                 --threshold 2 --key {key} -i {tx} -o {output}"
+            ))
+            .map(|()| output)
+        };
+        let alice = sign("alice").unwrap();
+        let carol = sign("carol").unwrap();
+        assert!(sign("mallory").is_err(), "{mallory} is not an owner");
+
+        // below the threshold, the partially signed transaction is kept as JSON
+        let partial = dir.path("partial.json");
+        wallet(&format!("multisig combine -i {alice} -o {partial}")).unwrap();
+        assert_eq!(read_json::<SignedTx>(&partial).signatures().len(), 1);
+
+        let output = dir.path("signed.hex");
+        wallet(&format!(
+            "multisig combine -i {partial} -i {carol} -o {output}"
+        ))
+        .unwrap();
+        let hex = std::fs::read_to_string(&output).unwrap();
+        let signed = SignedTx::decode(&alloy_primitives::hex::decode(hex).unwrap()).unwrap();
+        assert!(signed.verify().is_ok());
+        let treasury = Multisig::new(2, owners).unwrap();
+        assert_eq!(signed.sender(), &treasury.address());
+        assert_eq!(signed.transaction, read_json::<Transaction>(&tx));
+    }
+
+    #[test]
+    fn generate_vanity_address() {
+        let dir = TempDir::new();
+        let key = dir.path("key");
+        wallet(&format!(
+            "vanity --prefix a --regex F$ -t 2 --plaintext -o {key}"
+        ))
+        .unwrap();
+        let address = format!("{:x}", read_wallet(&key).address());
+        assert!(address.starts_with("0xa") && address.ends_with('f'));
+
+        assert!(wallet(&format!("vanity --prefix xyz --plaintext -o {key}")).is_err());
+        assert!(wallet(&format!("vanity --plaintext -o {key}")).is_err());
+    }
+
+    #[test]
+    fn manage_keyring_accounts() {
+        let dir = TempDir::new();
+        let password = dir.path("password");
+        std::fs::write(&password, "password\n").unwrap();
+        let alice = Wallet::random();
+        let keystore = dir.path("alice.json");
+        let json = alice
+            .to_keystore("password", Kdf::pbkdf2_with(1024))
+            .unwrap();
+        std::fs::write(&keystore, json.to_json().unwrap()).unwrap();
+
+        let keyring = |args: &str| wallet(&format!("keyring --dir {} {args}", dir.path("keyring")));
+        keyring(&format!(
+            "import -i {keystore} -l alice --password-file {password}"
+        ))
+        .unwrap();
+        keyring("list").unwrap();
+        keyring("rename alice treasury").unwrap();
+        // a label cannot look like an address
+        let address = Wallet::random().address();
+        assert!(keyring(&format!("rename treasury {address}")).is_err());
+        keyring("default treasury").unwrap();
+
+        // the default account is exported when none is named
+        let exported = dir.path("exported.json");
+        keyring(&format!("export -o {exported}")).unwrap();
+        let keystore: Keystore = read_json(&exported);
+        let unlocked = Wallet::from_keystore(&keystore, "password").unwrap();
+        assert_eq!(unlocked.address(), alice.address());
+
+        keyring("remove treasury").unwrap();
+        assert!(keyring("export").is_err());
+        assert!(keyring("remove treasury").is_err());
+    }
+
+    #[test]
+    fn manage_address_book() {
+        let dir = TempDir::new();
+        let path = dir.path("book.json");
+        let book = |args: &str| wallet(&format!("--address-book {path} book {args}"));
+        let bob = Wallet::random().address();
+
+        book(&format!("add bob {bob}")).unwrap();
+        book(&format!("add cold {} --watch", Wallet::random().address())).unwrap();
+        book("list").unwrap();
+        // a label cannot look like an address, nor be given twice
+        let address = Wallet::random().address();
+        assert!(book(&format!("add {address} {address}")).is_err());
+        assert!(book(&format!("add bob {address}")).is_err());
+
+        // labels are resolved wherever an address is expected
+        let tx = dir.path("tx.json");
+        wallet(&format!(
+            "--address-book {path} tx build --chain-id 1 --nonce 0 --to bob --value 10 -o {tx}"
+        ))
+        .unwrap();
+        assert_eq!(read_json::<Transaction>(&tx).to, bob);
+
+        book("remove bob").unwrap();
+        assert!(book("remove bob").is_err());
+    }
+}
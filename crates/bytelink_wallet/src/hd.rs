@@ -0,0 +1,252 @@
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use k256::ecdsa::SigningKey;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::{NonZeroScalar, Scalar};
+use sha2::Sha512;
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+type HmacSha512 = Hmac<Sha512>;
+
+/// Offset of the hardened child indexes, written `i'` in a derivation path.
+pub const HARDENED: u32 = 1 << 31;
+
+/// The default [BIP-44](https://github.com/bitcoin/bips/blob/master/bip-0044.mediawiki) account path
+/// `m / purpose' / coin_type' / account' / change`, the address index is appended to it.
+/// Bytelink addresses are ethereum addresses, so the ethereum coin type is used.
+pub const DEFAULT_PATH: &str = "m/44'/60'/0'/0";
+
+/// A [BIP-32](https://github.com/bitcoin/bips/blob/master/bip-0032.mediawiki) derivation path,
+/// i.e. `m/44'/60'/0'/0/0`. Hardened indexes are marked by `'` or `h`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// The path of the child at `index` of this path.
+    pub fn child(&self, index: u32) -> Self {
+        let mut path = self.0.clone();
+        path.push(index);
+        DerivationPath(path)
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parts = s.trim().split('/');
+        if parts.next() != Some("m") {
+            anyhow::bail!("Invalid derivation path: must start with \"m\", got {s}");
+        }
+
+        let indexes = parts
+            .map(|part| {
+                let (index, hardened) = match part.strip_suffix(['\'', 'h']) {
+                    Some(index) => (index, true),
+                    None => (part, false),
+                };
+                let index: u32 = index
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Invalid derivation path index: {part}"))?;
+                if index >= HARDENED {
+                    anyhow::bail!("Invalid derivation path index: {part} is out of range");
+                }
+                Ok(if hardened { index + HARDENED } else { index })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(DerivationPath(indexes))
+    }
+}
+
+impl std::fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            if *index >= HARDENED {
+                write!(f, "/{}'", index - HARDENED)?;
+            } else {
+                write!(f, "/{index}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An extended private key, i.e. a private key with its chain code.
+#[derive(Clone)]
+pub struct ExtendedKey {
+    key: SigningKey,
+    chain_code: [u8; 32],
+}
+
+impl ExtendedKey {
+    /// The master key derived from a seed, usually the [BIP-39](https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki) seed of a mnemonic.
+    pub fn master(seed: &[u8]) -> Result<Self> {
+        let mut mac = HmacSha512::new_from_slice(b"Bitcoin seed")?;
+        mac.update(seed);
+        Self::from_hmac(&mac.finalize().into_bytes())
+    }
+
+    fn from_hmac(output: &[u8]) -> Result<Self> {
+        let (key, chain_code) = output.split_at(32);
+        Ok(ExtendedKey {
+            key: SigningKey::from_slice(key)?,
+            chain_code: chain_code.try_into()?,
+        })
+    }
+
+    /// Derive the child private key at `index`, hardened if `index >= HARDENED`.
+    pub fn derive_child(&self, index: u32) -> Result<Self> {
+        let mut mac = HmacSha512::new_from_slice(&self.chain_code)?;
+        if index >= HARDENED {
+            mac.update(&[0]);
+            mac.update(&self.key.to_bytes());
+        } else {
+            let public = self.key.verifying_key().as_affine().to_encoded_point(true);
+            mac.update(public.as_bytes());
+        }
+        mac.update(&index.to_be_bytes());
+        let output = mac.finalize().into_bytes();
+        let (tweak, chain_code) = output.split_at(32);
+
+        // child = parse256(tweak) + parent (mod n), both must be valid keys
+        let tweak = SigningKey::from_slice(tweak)?;
+        let parent: Scalar = *self.key.as_nonzero_scalar().as_ref();
+        let child: Scalar = *tweak.as_nonzero_scalar().as_ref() + parent;
+        let child = Option::<NonZeroScalar>::from(NonZeroScalar::new(child))
+            .ok_or_else(|| anyhow::anyhow!("Invalid child key at index {index}"))?;
+
+        Ok(ExtendedKey {
+            key: SigningKey::from(child),
+            chain_code: chain_code.try_into()?,
+        })
+    }
+
+    pub fn derive(&self, path: &DerivationPath) -> Result<Self> {
+        path.0
+            .iter()
+            .try_fold(self.clone(), |key, index| key.derive_child(*index))
+    }
+
+    pub fn key(&self) -> &SigningKey {
+        &self.key
+    }
+}
+
+/// Generate a new English [BIP-39](https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki)
+/// mnemonic of 12 or 24 words.
+pub fn generate_mnemonic(words: usize) -> Result<bip39::Mnemonic> {
+    let entropy = match words {
+        12 => rand::random::<[u8; 16]>().to_vec(),
+        24 => rand::random::<[u8; 32]>().to_vec(),
+        _ => anyhow::bail!("Invalid mnemonic length: expected 12 or 24 words, got {words}"),
+    };
+    Ok(bip39::Mnemonic::from_entropy(&entropy)?)
+}
+
+/// Derive the key at `path` from a mnemonic phrase, protected by an optional passphrase.
+pub fn derive_from_mnemonic(
+    phrase: &str,
+    passphrase: &str,
+    path: &DerivationPath,
+) -> Result<ExtendedKey> {
+    let mnemonic = bip39::Mnemonic::parse_normalized(phrase)?;
+    ExtendedKey::master(&mnemonic.to_seed_normalized(passphrase))?.derive(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hd::{DEFAULT_PATH, DerivationPath, ExtendedKey, derive_from_mnemonic};
+    use bytelink_crypto::address::Address;
+
+    #[test]
+    fn parse_derivation_path() {
+        let path: DerivationPath = "m/44'/60h/0'/0/7".parse().unwrap();
+        assert_eq!(path.to_string(), "m/44'/60'/0'/0/7");
+        assert_eq!(
+            DEFAULT_PATH.parse::<DerivationPath>().unwrap().child(7),
+            path
+        );
+
+        assert!("44'/60'".parse::<DerivationPath>().is_err());
+        assert!("m/x".parse::<DerivationPath>().is_err());
+        assert!("m/2147483648".parse::<DerivationPath>().is_err());
+    }
+
+    #[test]
+    fn bip32_test_vector_1() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtendedKey::master(&seed).unwrap();
+
+        let vectors = [
+            (
+                "m",
+                "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35",
+            ),
+            (
+                "m/0'",
+                "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea",
+            ),
+            (
+                "m/0'/1",
+                "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368",
+            ),
+            (
+                "m/0'/1/2'",
+                "cbce0d719ecf7431d88e6a89fa1483e02e35092af60c042b1df2ff59fa424dca",
+            ),
+            (
+                "m/0'/1/2'/2",
+                "0f479245fb19a38a1954c5c7c0ebab2f9bdfd96a17563ef28a6a4b1a2a764ef4",
+            ),
+            (
+                "m/0'/1/2'/2/1000000000",
+                "471b76e389e528d6de6d816857e012c5455051cad6660850e58372a6c3e6e7c8",
+            ),
+        ];
+
+        for (path, key) in vectors {
+            let derived = master.derive(&path.parse().unwrap()).unwrap();
+            assert_eq!(hex::encode(derived.key().to_bytes()), key, "{path}");
+        }
+    }
+
+    #[test]
+    fn bip39_test_vector() {
+        let mnemonic = bip39::Mnemonic::parse_normalized(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        )
+        .unwrap();
+        assert_eq!(
+            hex::encode(mnemonic.to_seed_normalized("TREZOR")),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+    }
+
+    #[test]
+    fn ethereum_accounts() {
+        let phrase = "test test test test test test test test test test test junk";
+        let path: DerivationPath = DEFAULT_PATH.parse().unwrap();
+
+        let expected = [
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+            "0x70997970C51812dc3A010C7d01b50e0d17dc79C8",
+            "0x3C44CdDdB6a900fa2b585dd299e03d12FA4293BC",
+        ];
+
+        for (index, address) in expected.into_iter().enumerate() {
+            let key = derive_from_mnemonic(phrase, "", &path.child(index as u32)).unwrap();
+            assert_eq!(Address::from_private_key(key.key()).checksummed(), address);
+        }
+    }
+
+    #[test]
+    fn reject_invalid_mnemonic() {
+        let path: DerivationPath = DEFAULT_PATH.parse().unwrap();
+        // wrong checksum word
+        let phrase = "test test test test test test test test test test test test";
+        assert!(derive_from_mnemonic(phrase, "", &path).is_err());
+    }
+}
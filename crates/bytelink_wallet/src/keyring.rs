@@ -0,0 +1,255 @@
+use crate::keystore::{Keystore, create_secret_file};
+use crate::wallet::Wallet;
+use anyhow::Result;
+use bytelink_crypto::address::Address;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const INDEX_FILE: &str = "keyring.json";
+
+/// A named account of the [`Keyring`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Account {
+    pub label: String,
+    pub address: Address,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Index {
+    default: Option<Address>,
+    accounts: Vec<Account>,
+}
+
+/// A directory of encrypted keys, each one saved as a keystore file named after its
+/// checksummed address, next to an index of the account labels:
+/// ```text
+/// keyring/
+/// ├── keyring.json
+/// ├── 0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266.json
+/// └── 0x70997970C51812dc3A010C7d01b50e0d17dc79C8.json
+/// ```
+/// Accounts are referred to either by label or by address.
+pub struct Keyring {
+    dir: PathBuf,
+    index: Index,
+}
+
+/// A label of an account or of an address book entry must not be empty nor look like an address,
+/// so that looking it up is never ambiguous.
+pub fn validate_label(label: &str) -> Result<()> {
+    if label.is_empty() || Address::parse_checksummed(label).is_ok() {
+        anyhow::bail!("Invalid label: {label:?}");
+    }
+    Ok(())
+}
+
+impl Keyring {
+    /// Open the keyring in the directory, which is created if missing.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        let index = match std::fs::read_to_string(dir.join(INDEX_FILE)) {
+            Ok(content) => serde_json::from_str(&content)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Index::default(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Keyring { dir, index })
+    }
+
+    pub fn accounts(&self) -> &[Account] {
+        &self.index.accounts
+    }
+
+    pub fn default_account(&self) -> Option<&Account> {
+        let default = self.index.default.as_ref()?;
+        self.index.accounts.iter().find(|a| &a.address == default)
+    }
+
+    /// Find an account by label or by checksummed address.
+    pub fn account(&self, name: &str) -> Result<&Account> {
+        let address = Address::parse_checksummed(name).ok();
+        self.index
+            .accounts
+            .iter()
+            .find(|a| a.label == name || Some(&a.address) == address.as_ref())
+            .ok_or_else(|| anyhow::anyhow!("Unknown account: {name}"))
+    }
+
+    /// Add an encrypted key under a new label, the first account becomes the default one.
+    /// The keystore must decrypt with the password, so that a wrong key is never stored.
+    pub fn import(&mut self, label: &str, keystore: Keystore, password: &str) -> Result<&Account> {
+        validate_label(label)?;
+        if self.index.accounts.iter().any(|a| a.label == label) {
+            anyhow::bail!("Account already exists: {label}");
+        }
+
+        let address = Wallet::from_keystore(&keystore, password)?.address();
+        if let Some(account) = self.index.accounts.iter().find(|a| a.address == address) {
+            anyhow::bail!(
+                "Account already exists: {} is {}",
+                address.checksummed(),
+                account.label
+            );
+        }
+
+        let mut file = create_secret_file(self.keystore_path(&address))?;
+        file.write_all(keystore.to_json()?.as_bytes())?;
+
+        if self.index.default.is_none() {
+            self.index.default = Some(address.clone());
+        }
+        self.index.accounts.push(Account {
+            label: label.to_string(),
+            address,
+        });
+        self.save()?;
+        Ok(self.index.accounts.last().expect("account was just pushed"))
+    }
+
+    /// The keystore of an account, still encrypted.
+    pub fn export(&self, name: &str) -> Result<Keystore> {
+        let account = self.account(name)?;
+        Keystore::from_json(&std::fs::read_to_string(
+            self.keystore_path(&account.address),
+        )?)
+    }
+
+    pub fn rename(&mut self, name: &str, label: &str) -> Result<()> {
+        validate_label(label)?;
+        if self.account(label).is_ok() {
+            anyhow::bail!("Account already exists: {label}");
+        }
+        let address = self.account(name)?.address.clone();
+        let account = self
+            .index
+            .accounts
+            .iter_mut()
+            .find(|a| a.address == address)
+            .expect("account was just found");
+        account.label = label.to_string();
+        self.save()
+    }
+
+    /// Remove an account and delete its keystore, the default account is unset if it is removed.
+    pub fn remove(&mut self, name: &str) -> Result<Account> {
+        let address = self.account(name)?.address.clone();
+        std::fs::remove_file(self.keystore_path(&address))?;
+
+        if self.index.default.as_ref() == Some(&address) {
+            self.index.default = None;
+        }
+        let position = self
+            .index
+            .accounts
+            .iter()
+            .position(|a| a.address == address)
+            .expect("account was just found");
+        let account = self.index.accounts.remove(position);
+        self.save()?;
+        Ok(account)
+    }
+
+    pub fn set_default(&mut self, name: &str) -> Result<()> {
+        self.index.default = Some(self.account(name)?.address.clone());
+        self.save()
+    }
+
+    fn keystore_path(&self, address: &Address) -> PathBuf {
+        self.dir.join(format!("{}.json", address.checksummed()))
+    }
+
+    fn save(&self) -> Result<()> {
+        let mut file = std::fs::File::create(self.dir.join(INDEX_FILE))?;
+        file.write_all(serde_json::to_string_pretty(&self.index)?.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::keyring::Keyring;
+    use crate::keystore::{Kdf, Keystore};
+    use crate::wallet::Wallet;
+    use std::path::PathBuf;
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("bytelink-keyring-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn keystore(wallet: &Wallet) -> Keystore {
+        wallet
+            .to_keystore("password", Kdf::pbkdf2_with(1024))
+            .unwrap()
+    }
+
+    #[test]
+    fn manage_accounts() {
+        let dir = temp_dir();
+        let (alice, bob) = (Wallet::random(), Wallet::random());
+
+        let mut keyring = Keyring::open(&dir).unwrap();
+        keyring
+            .import("alice", keystore(&alice), "password")
+            .unwrap();
+        keyring.import("bob", keystore(&bob), "password").unwrap();
+
+        // the index is persisted
+        let mut keyring = Keyring::open(&dir).unwrap();
+        assert_eq!(keyring.accounts().len(), 2);
+        assert_eq!(keyring.default_account().unwrap().label, "alice");
+        assert_eq!(
+            keyring.account(&bob.address().checksummed()).unwrap().label,
+            "bob"
+        );
+
+        let keystore = keyring.export("bob").unwrap();
+        let unlocked = Wallet::from_keystore(&keystore, "password").unwrap();
+        assert_eq!(unlocked.address(), bob.address());
+
+        keyring.rename("bob", "carol").unwrap();
+        assert!(keyring.account("bob").is_err());
+        keyring.set_default("carol").unwrap();
+        assert_eq!(keyring.default_account().unwrap().address, bob.address());
+
+        keyring.remove("carol").unwrap();
+        assert!(keyring.default_account().is_none());
+        assert!(keyring.export("carol").is_err());
+        assert_eq!(keyring.accounts().len(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reject_duplicates() {
+        let dir = temp_dir();
+        let alice = Wallet::random();
+
+        let mut keyring = Keyring::open(&dir).unwrap();
+        keyring
+            .import("alice", keystore(&alice), "password")
+            .unwrap();
+        assert!(
+            keyring
+                .import("alice", keystore(&Wallet::random()), "password")
+                .is_err()
+        );
+        assert!(
+            keyring
+                .import("other", keystore(&alice), "password")
+                .is_err()
+        );
+        assert!(
+            keyring
+                .import("other", keystore(&Wallet::random()), "wrong")
+                .is_err()
+        );
+        assert!(keyring.rename("alice", "alice").is_err());
+        assert!(keyring.rename("alice", "").is_err());
+        let address = Wallet::random().address().checksummed();
+        assert!(keyring.rename("alice", &address).is_err());
+        assert_eq!(keyring.account("alice").unwrap().address, alice.address());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,322 @@
+use aes::cipher::{KeyIvInit, StreamCipher};
+use alloy_primitives::keccak256;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+#[cfg(unix)]
+use std::fs::OpenOptions;
+use std::path::Path;
+
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+
+const CIPHER: &str = "aes-128-ctr";
+const PRF: &str = "hmac-sha256";
+
+/// Create or truncate a file holding a secret, readable and writable by the owner only.
+pub fn create_secret_file<P: AsRef<Path>>(path: P) -> Result<File> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(path)?;
+        // the mode only applies to a new file, restrict an existing one before writing to it
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        Ok(file)
+    }
+    #[cfg(not(unix))]
+    {
+        Ok(File::create(path)?)
+    }
+}
+
+/// An encrypted key file following the
+/// [Web3 Secret Storage Definition](https://ethereum.org/en/developers/docs/data-structures-and-encoding/web3-secret-storage/) (version 3).
+///
+/// The password is stretched by the KDF into a 32-byte derived key, whose first half is the
+/// AES-128-CTR key and second half authenticates the ciphertext:
+/// ```text
+/// mac = keccak256(derived_key[16..32] | ciphertext)
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    pub crypto: Crypto,
+    pub id: String,
+    pub version: u8,
+    /// the address of the key, without "0x" as other tools write it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Crypto {
+    pub cipher: String,
+    pub cipherparams: CipherParams,
+    #[serde(with = "hex")]
+    pub ciphertext: Vec<u8>,
+    #[serde(flatten)]
+    pub kdf: Kdf,
+    #[serde(with = "hex")]
+    pub mac: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CipherParams {
+    #[serde(with = "hex")]
+    pub iv: Vec<u8>,
+}
+
+/// The key derivation function stretching the password, with its parameters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kdf", content = "kdfparams", rename_all = "lowercase")]
+pub enum Kdf {
+    Scrypt {
+        dklen: u8,
+        n: u32,
+        r: u32,
+        p: u32,
+        #[serde(with = "hex")]
+        salt: Vec<u8>,
+    },
+    Pbkdf2 {
+        c: u32,
+        dklen: u8,
+        prf: String,
+        #[serde(with = "hex")]
+        salt: Vec<u8>,
+    },
+}
+
+impl Kdf {
+    /// scrypt with the parameters used by geth, `n = 2^18`, `r = 8`, `p = 1`.
+    pub fn scrypt() -> Self {
+        Self::scrypt_with(18, 8, 1)
+    }
+
+    /// scrypt with `n = 2^log_n`, a fresh random salt is generated.
+    pub fn scrypt_with(log_n: u8, r: u32, p: u32) -> Self {
+        Kdf::Scrypt {
+            dklen: 32,
+            n: 1 << log_n,
+            r,
+            p,
+            salt: rand::random::<[u8; 32]>().to_vec(),
+        }
+    }
+
+    /// PBKDF2-HMAC-SHA256 with the iteration count used by geth, `c = 2^18`.
+    pub fn pbkdf2() -> Self {
+        Self::pbkdf2_with(1 << 18)
+    }
+
+    /// PBKDF2-HMAC-SHA256 with `c` iterations, a fresh random salt is generated.
+    pub fn pbkdf2_with(c: u32) -> Self {
+        Kdf::Pbkdf2 {
+            c,
+            dklen: 32,
+            prf: PRF.to_string(),
+            salt: rand::random::<[u8; 32]>().to_vec(),
+        }
+    }
+
+    fn derive_key(&self, password: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Kdf::Scrypt {
+                dklen,
+                n,
+                r,
+                p,
+                salt,
+            } => {
+                if !n.is_power_of_two() || *n < 2 {
+                    anyhow::bail!("Invalid keystore: scrypt n must be a power of two, got {n}");
+                }
+                let params = scrypt::Params::new(n.ilog2() as u8, *r, *p, *dklen as usize)?;
+                let mut key = vec![0u8; *dklen as usize];
+                scrypt::scrypt(password, salt, &params, &mut key)?;
+                Ok(key)
+            }
+            Kdf::Pbkdf2 {
+                c,
+                dklen,
+                prf,
+                salt,
+            } => {
+                if prf != PRF {
+                    anyhow::bail!("Unsupported keystore pbkdf2 prf: {prf}");
+                }
+                let mut key = vec![0u8; *dklen as usize];
+                pbkdf2::pbkdf2_hmac::<sha2::Sha256>(password, salt, *c, &mut key);
+                Ok(key)
+            }
+        }
+    }
+}
+
+fn mac(derived_key: &[u8], ciphertext: &[u8]) -> Vec<u8> {
+    keccak256([&derived_key[16..32], ciphertext].concat()).to_vec()
+}
+
+fn apply_cipher(derived_key: &[u8], iv: &[u8], data: &mut [u8]) -> Result<()> {
+    let mut cipher = Aes128Ctr::new_from_slices(&derived_key[..16], iv)
+        .map_err(|_| anyhow::anyhow!("Invalid keystore: iv must be 16 bytes"))?;
+    cipher.apply_keystream(data);
+    Ok(())
+}
+
+impl Keystore {
+    /// Encrypt the secret with a key derived from the password.
+    pub fn encrypt(secret: &[u8], password: &str, kdf: Kdf) -> Result<Self> {
+        let derived_key = kdf.derive_key(password.as_bytes())?;
+        let iv = rand::random::<[u8; 16]>().to_vec();
+
+        let mut ciphertext = secret.to_vec();
+        apply_cipher(&derived_key, &iv, &mut ciphertext)?;
+
+        Ok(Keystore {
+            crypto: Crypto {
+                cipher: CIPHER.to_string(),
+                cipherparams: CipherParams { iv },
+                mac: mac(&derived_key, &ciphertext),
+                ciphertext,
+                kdf,
+            },
+            id: uuid::Uuid::new_v4().to_string(),
+            version: 3,
+            address: None,
+        })
+    }
+
+    /// Decrypt the secret, the password is checked against the MAC first.
+    pub fn decrypt(&self, password: &str) -> Result<Vec<u8>> {
+        if self.version != 3 {
+            anyhow::bail!("Unsupported keystore version: {}", self.version);
+        }
+        if self.crypto.cipher != CIPHER {
+            anyhow::bail!("Unsupported keystore cipher: {}", self.crypto.cipher);
+        }
+
+        let derived_key = self.crypto.kdf.derive_key(password.as_bytes())?;
+        if derived_key.len() < 32 {
+            anyhow::bail!("Invalid keystore: derived key must be at least 32 bytes");
+        }
+        if mac(&derived_key, &self.crypto.ciphertext) != self.crypto.mac {
+            anyhow::bail!("Wrong password: keystore MAC mismatch");
+        }
+
+        let mut secret = self.crypto.ciphertext.clone();
+        apply_cipher(&derived_key, &self.crypto.cipherparams.iv, &mut secret)?;
+        Ok(secret)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self> {
+        Ok(serde_json::from_str(json)?)
+    }
+
+    /// Parse the content of a key file, `None` if it is not JSON, i.e. a plaintext hex key.
+    /// A malformed keystore is an error rather than being taken for a hex key.
+    pub fn from_key_file(content: &str) -> Result<Option<Self>> {
+        if !content.trim_start().starts_with('{') {
+            return Ok(None);
+        }
+        Self::from_json(content).map(Some)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::keystore::{Kdf, Keystore, create_secret_file};
+
+    // test vector of the Web3 Secret Storage Definition, its scrypt counterpart uses
+    // `r = 1` with `n = 2^18`, which is rejected as insecure by RFC 7914 (`n < 2^(16 r)`)
+    const PASSWORD: &str = "testpassword";
+    const SECRET: &str = "7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d";
+
+    const PBKDF2_VECTOR: &str = r#"{
+        "crypto" : {
+            "cipher" : "aes-128-ctr",
+            "cipherparams" : { "iv" : "6087dab2f9fdbbfaddc31a909735c1e6" },
+            "ciphertext" : "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+            "kdf" : "pbkdf2",
+            "kdfparams" : {
+                "c" : 262144,
+                "dklen" : 32,
+                "prf" : "hmac-sha256",
+                "salt" : "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+            },
+            "mac" : "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+        },
+        "id" : "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+        "version" : 3
+    }"#;
+
+    #[test]
+    fn decrypt_pbkdf2_vector() {
+        let keystore = Keystore::from_json(PBKDF2_VECTOR).unwrap();
+        let secret = keystore.decrypt(PASSWORD).unwrap();
+        assert_eq!(hex::encode(secret), SECRET);
+    }
+
+    #[test]
+    fn wrong_password() {
+        let keystore = Keystore::from_json(PBKDF2_VECTOR).unwrap();
+        let err = keystore.decrypt("not the password").unwrap_err();
+        assert!(err.to_string().contains("Wrong password"));
+    }
+
+    #[test]
+    fn encrypt_roundtrip() {
+        let secret = hex::decode(SECRET).unwrap();
+        for kdf in [Kdf::scrypt_with(10, 8, 1), Kdf::pbkdf2_with(1024)] {
+            let keystore = Keystore::encrypt(&secret, PASSWORD, kdf).unwrap();
+            let json = keystore.to_json().unwrap();
+            let decoded = Keystore::from_json(&json).unwrap();
+            assert_eq!(decoded, keystore);
+            assert_eq!(decoded.decrypt(PASSWORD).unwrap(), secret);
+            assert!(decoded.decrypt("wrong").is_err());
+        }
+    }
+
+    #[test]
+    fn reject_tampered_ciphertext() {
+        let secret = hex::decode(SECRET).unwrap();
+        let mut keystore = Keystore::encrypt(&secret, PASSWORD, Kdf::pbkdf2_with(1024)).unwrap();
+        keystore.crypto.ciphertext[0] ^= 1;
+        assert!(keystore.decrypt(PASSWORD).is_err());
+    }
+
+    #[test]
+    fn parse_key_file() {
+        assert!(Keystore::from_key_file(PBKDF2_VECTOR).unwrap().is_some());
+        assert!(Keystore::from_key_file(SECRET).unwrap().is_none());
+        // a malformed keystore is not taken for a hex key
+        assert!(Keystore::from_key_file(r#"{ "version": 3 }"#).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn secret_file_is_private() {
+        use std::io::Write;
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("bytelink-secret-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "public").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        create_secret_file(&path)
+            .unwrap()
+            .write_all(SECRET.as_bytes())
+            .unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), SECRET);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -1,16 +1,15 @@
-use crate::cli::{Cli, Command};
+use crate::cli::Cli;
 use clap::Parser;
 
+mod addressbook;
 mod cli;
 mod cmd;
+mod hd;
+mod keyring;
+mod keystore;
+mod vanity;
 mod wallet;
 
 fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
-
-    match cli.cmd {
-        Command::Create(opts) => cmd::generate_wallet(&opts)?,
-    }
-
-    Ok(())
+    cmd::run(Cli::parse())
 }
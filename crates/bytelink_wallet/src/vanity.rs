@@ -0,0 +1,187 @@
+use crate::wallet::Wallet;
+use anyhow::Result;
+use regex::{Regex, RegexBuilder};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// The pattern a vanity address must match, on its 40 hex characters without "0x".
+///
+/// The match is case-insensitive unless `case_sensitive` is set, in which case it is done
+/// against the [EIP-55](https://eips.ethereum.org/EIPS/eip-55) checksummed form.
+pub struct Pattern {
+    prefix: String,
+    suffix: String,
+    regex: Option<Regex>,
+    case_sensitive: bool,
+}
+
+impl Pattern {
+    pub fn new(
+        prefix: Option<&str>,
+        suffix: Option<&str>,
+        regex: Option<&str>,
+        case_sensitive: bool,
+    ) -> Result<Self> {
+        let normalize = |part: Option<&str>| -> Result<String> {
+            let part = part.unwrap_or_default();
+            if !part.chars().all(|c| c.is_ascii_hexdigit()) {
+                anyhow::bail!("Invalid vanity pattern: {part} is not hex");
+            }
+            Ok(if case_sensitive {
+                part.to_string()
+            } else {
+                part.to_ascii_lowercase()
+            })
+        };
+
+        let prefix = normalize(prefix.map(|p| p.strip_prefix("0x").unwrap_or(p)))?;
+        let suffix = normalize(suffix)?;
+        if prefix.len() + suffix.len() > 40 {
+            anyhow::bail!("Invalid vanity pattern: longer than an address");
+        }
+
+        let regex = regex
+            .map(|regex| {
+                RegexBuilder::new(regex)
+                    .case_insensitive(!case_sensitive)
+                    .build()
+            })
+            .transpose()?;
+        if prefix.is_empty() && suffix.is_empty() && regex.is_none() {
+            anyhow::bail!("Invalid vanity pattern: empty");
+        }
+
+        Ok(Pattern {
+            prefix,
+            suffix,
+            regex,
+            case_sensitive,
+        })
+    }
+
+    pub fn matches(&self, wallet: &Wallet) -> bool {
+        let address = if self.case_sensitive {
+            wallet.address().checksummed()
+        } else {
+            format!("{:x}", wallet.address())
+        };
+        let hex = &address[2..];
+        hex.starts_with(&self.prefix)
+            && hex.ends_with(&self.suffix)
+            && self.regex.as_ref().is_none_or(|regex| regex.is_match(hex))
+    }
+
+    /// The expected number of keys to try before a match, unknown with a regex.
+    ///
+    /// Every hex character divides the odds by 16, and the case of a letter
+    /// in the checksummed form divides them by 2 again.
+    pub fn difficulty(&self) -> Option<f64> {
+        if self.regex.is_some() {
+            return None;
+        }
+        let chars = self.prefix.chars().chain(self.suffix.chars());
+        Some(chars.fold(1.0, |odds, c| {
+            if self.case_sensitive && c.is_ascii_alphabetic() {
+                odds * 32.0
+            } else {
+                odds * 16.0
+            }
+        }))
+    }
+}
+
+/// Try random keys on `threads` threads until one matches the pattern.
+/// `progress` is called about every `interval` with the number of keys tried and the elapsed time.
+pub fn search<F>(pattern: &Pattern, threads: usize, interval: Duration, progress: F) -> Wallet
+where
+    F: Fn(u64, Duration),
+{
+    let attempts = AtomicU64::new(0);
+    let done = AtomicBool::new(false);
+    let found = Mutex::new(None);
+    let start = Instant::now();
+
+    std::thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| {
+                while !done.load(Ordering::Relaxed) {
+                    let wallet = Wallet::random();
+                    attempts.fetch_add(1, Ordering::Relaxed);
+                    if pattern.matches(&wallet) {
+                        found.lock().unwrap().get_or_insert(wallet);
+                        done.store(true, Ordering::Relaxed);
+                    }
+                }
+            });
+        }
+
+        let mut last = Instant::now();
+        while !done.load(Ordering::Relaxed) {
+            std::thread::sleep(Duration::from_millis(10).min(interval));
+            if last.elapsed() >= interval {
+                progress(attempts.load(Ordering::Relaxed), start.elapsed());
+                last = Instant::now();
+            }
+        }
+    });
+
+    found
+        .into_inner()
+        .unwrap()
+        .expect("search only stops once a key is found")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vanity::{Pattern, search};
+    use crate::wallet::Wallet;
+    use std::time::Duration;
+
+    #[test]
+    fn invalid_pattern() {
+        assert!(Pattern::new(None, None, None, false).is_err());
+        assert!(Pattern::new(Some("xyz"), None, None, false).is_err());
+        assert!(Pattern::new(None, None, Some("("), false).is_err());
+        assert!(Pattern::new(Some(&"a".repeat(41)), None, None, false).is_err());
+    }
+
+    #[test]
+    fn difficulty() {
+        let pattern = Pattern::new(Some("0xab"), Some("1"), None, false).unwrap();
+        assert_eq!(pattern.difficulty(), Some(4096.0));
+        let pattern = Pattern::new(Some("Ab"), Some("1"), None, true).unwrap();
+        assert_eq!(pattern.difficulty(), Some(16384.0));
+        let pattern = Pattern::new(None, None, Some("^00"), false).unwrap();
+        assert_eq!(pattern.difficulty(), None);
+    }
+
+    #[test]
+    fn match_address() {
+        // 0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266
+        let wallet =
+            Wallet::from_hex("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80")
+                .unwrap();
+
+        let matches = |prefix, suffix, regex, case_sensitive| {
+            Pattern::new(prefix, suffix, regex, case_sensitive)
+                .unwrap()
+                .matches(&wallet)
+        };
+        assert!(matches(Some("F39F"), None, None, false));
+        assert!(matches(Some("f39F"), Some("66"), None, true));
+        assert!(!matches(Some("F39F"), None, None, true));
+        assert!(matches(None, None, Some("^f3.*2266$"), false));
+        assert!(matches(None, None, Some("^F39F"), false));
+        assert!(!matches(None, None, Some("^F39F"), true));
+        assert!(matches(None, None, Some("^f39Fd6"), true));
+        assert!(!matches(Some("f39f"), Some("00"), None, false));
+    }
+
+    #[test]
+    fn search_prefix() {
+        let pattern = Pattern::new(Some("a"), None, None, false).unwrap();
+        let wallet = search(&pattern, 2, Duration::from_secs(60), |_, _| {});
+        assert!(format!("{:x}", wallet.address()).starts_with("0xa"));
+    }
+}
@@ -1,5 +1,10 @@
+use crate::hd::{DerivationPath, derive_from_mnemonic};
+use crate::keystore::{Kdf, Keystore};
+use alloy_primitives::B256;
 use anyhow::Result;
 use bytelink_crypto::address::Address;
+use bytelink_crypto::signature::Signature;
+use bytelink_crypto::signer::{Signer, SignerSync};
 use k256::ecdsa::SigningKey;
 use k256::elliptic_curve::rand_core::OsRng;
 
@@ -26,11 +31,48 @@ impl Wallet {
         let key = SigningKey::from_slice(&bytes)?;
         Ok(Wallet { key })
     }
+
+    /// Restore the wallet at `path` of a [BIP-39](https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki)
+    /// mnemonic, the passphrase is empty if not set.
+    pub fn from_mnemonic(phrase: &str, passphrase: &str, path: &DerivationPath) -> Result<Self> {
+        let key = derive_from_mnemonic(phrase, passphrase, path)?;
+        Ok(Wallet {
+            key: key.key().clone(),
+        })
+    }
+
+    /// A [`Signer`] holding a copy of the private key.
+    pub fn signer(&self) -> Result<Signer> {
+        Signer::from_slice(&self.key.to_bytes())
+    }
+
+    /// Encrypt the private key into a [`Keystore`] protected by the password.
+    pub fn to_keystore(&self, password: &str, kdf: Kdf) -> Result<Keystore> {
+        let mut keystore = Keystore::encrypt(&self.key.to_bytes(), password, kdf)?;
+        keystore.address = Some(format!("{:x}", self.address())[2..].to_string());
+        Ok(keystore)
+    }
+
+    pub fn from_keystore(keystore: &Keystore, password: &str) -> Result<Self> {
+        let bytes = keystore.decrypt(password)?;
+        let key = SigningKey::from_slice(&bytes)?;
+        Ok(Wallet { key })
+    }
+}
+
+impl SignerSync for Wallet {
+    fn sign_hash_sync(&self, hash: &B256) -> Result<Signature> {
+        self.signer()?.sign_hash_sync(hash)
+    }
 }
 
 #[cfg(test)]
 mod test {
+    use crate::hd::{DEFAULT_PATH, DerivationPath};
+    use crate::keystore::Kdf;
     use crate::wallet::Wallet;
+    use bytelink_crypto::signer::SignerSync;
+    use bytelink_node::database::transaction::{SignedTx, Transaction};
 
     #[test]
     fn wallet_identity() {
@@ -39,4 +81,58 @@ mod test {
         let recover = Wallet::from_hex(&hex).unwrap();
         assert_eq!(wallet.address(), recover.address());
     }
+
+    #[test]
+    fn keystore_identity() {
+        let wallet = Wallet::random();
+        let keystore = wallet
+            .to_keystore("password", Kdf::pbkdf2_with(1024))
+            .unwrap();
+        let recover = Wallet::from_keystore(&keystore, "password").unwrap();
+        assert_eq!(wallet.address(), recover.address());
+        assert!(Wallet::from_keystore(&keystore, "wrong").is_err());
+    }
+
+    #[test]
+    fn mnemonic_wallet() {
+        let phrase = "test test test test test test test test test test test junk";
+        let path = DEFAULT_PATH.parse::<DerivationPath>().unwrap().child(0);
+        let wallet = Wallet::from_mnemonic(phrase, "", &path).unwrap();
+        assert_eq!(
+            wallet.address().checksummed(),
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+        );
+
+        let protected = Wallet::from_mnemonic(phrase, "passphrase", &path).unwrap();
+        assert_ne!(wallet.address(), protected.address());
+    }
+
+    #[test]
+    fn sign_message() {
+        let wallet = Wallet::random();
+        let signature = wallet.eip191_sign_msg(b"I own this key").unwrap();
+        let recovered = signature
+            .recover_address_from_msg(b"I own this key")
+            .unwrap();
+        assert_eq!(recovered, wallet.address());
+        let recovered = signature
+            .recover_address_from_msg(b"I own that key")
+            .unwrap();
+        assert_ne!(recovered, wallet.address());
+    }
+
+    #[test]
+    fn sign_transaction() {
+        let wallet = Wallet::random();
+        let transaction = Transaction {
+            data: vec![0xde, 0xad],
+            ..Transaction::new(0, Wallet::random().address(), 100, 1)
+        };
+
+        let signed = transaction.sign_by(&wallet).unwrap();
+        let decoded = SignedTx::decode(&signed.encode()).unwrap();
+        assert_eq!(decoded, signed);
+        assert_eq!(decoded.sender(), &wallet.address());
+        assert!(decoded.verify().is_ok());
+    }
 }